@@ -0,0 +1,122 @@
+use async_graphql::*;
+use async_graphql_actix_web::{GraphQLRequest, GraphQLResponse};
+use actix_web::{web, HttpResponse, Result as ActixResult, HttpRequest, HttpMessage};
+use shared::{
+    app::AppState,
+    metrics::GraphQLMetrics,
+    database::services::user_service::UserService,
+    models::{
+        user::{
+            GraphQLUser, TimeOffsetDateTime,
+        },
+        mutation::Mutation,
+    },
+    error::{Error as AppError, GraphQLErrorCodes},
+    auth::{CurrentUser, Claims, Permission, PermissionGuard},
+};
+
+pub type MySchema = Schema<QueryRoot, Mutation, EmptySubscription>;
+
+async fn graphql_handler(
+    schema: web::Data<MySchema>,
+    req: HttpRequest,
+    payload: GraphQLRequest,
+) -> GraphQLResponse {
+    
+    let mut graphql_request = payload.into_inner();
+
+    if let Some(current_user) = req.extensions().get::<CurrentUser>() {
+        graphql_request = graphql_request.data(current_user.clone());
+    }
+    if let Some(claims) = req.extensions().get::<Claims>() {
+        graphql_request = graphql_request.data(claims.clone());
+    }
+
+    let response = schema.execute(graphql_request).await;
+    
+    response.into()
+}
+
+pub struct QueryRoot;
+
+#[Object]
+impl QueryRoot {
+    async fn hello(&self) -> &str {
+        "Hello, GraphQL!"
+    }
+
+    async fn me(&self, ctx: &Context<'_>) -> Result<GraphQLUser> {
+        let current_user = ctx.data::<CurrentUser>()
+            .map_err(|_| AppError::Unauthorized("Not authenticated".to_string()).extend())?;
+
+        Ok(GraphQLUser {
+            id: current_user.id.into(),
+            username: current_user.username.clone(),
+            email: current_user.email.clone(),
+            created_at: TimeOffsetDateTime(current_user.created_at),
+            updated_at: TimeOffsetDateTime(current_user.updated_at),
+        })
+    }
+
+    #[graphql(guard = "PermissionGuard::new(Permission::ReadUser)")]
+    async fn user(&self, ctx: &Context<'_>, id: ID) -> Result<Option<GraphQLUser>> {
+        let user_service = ctx.data::<UserService>()?;
+        let id_str = id.0;
+
+        if let Some(user_profile) = user_service.find_by_id(&id_str).await.extend()? {
+            Ok(Some(user_profile.into()))
+        } else {
+            Ok(None)
+        }
+    }
+
+    #[graphql(guard = "PermissionGuard::new(Permission::ReadUser)")]
+    async fn users(&self, ctx: &Context<'_>, ids: Vec<ID>) -> Result<Vec<GraphQLUser>> {
+        let user_service = ctx.data::<UserService>()?;
+        let ids_strs: Vec<&str> = ids.iter().map(|id| id.as_str()).collect();
+
+        let user_profiles = user_service.find_by_ids(&ids_strs).await.extend()?;
+        let users: Vec<GraphQLUser> = user_profiles.into_iter().map(|user_profile|user_profile.into()).collect();
+
+        Ok(users)
+    }
+
+    #[graphql(guard = "PermissionGuard::new(Permission::ReadUser)")]
+    async fn find_all(&self, ctx: &Context<'_>) -> Result<Vec<GraphQLUser>> {
+        let user_service = ctx.data::<UserService>()?;
+
+        let user_profiles = user_service.find_all().await.extend()?;
+        let users: Vec<GraphQLUser> = user_profiles.into_iter().map(|user_profile|user_profile.into()).collect();
+
+        Ok(users)
+    }
+}
+
+pub fn build_schema(state: &AppState) -> MySchema {
+    Schema::build(QueryRoot, Mutation, EmptySubscription)
+        .data(state.pool.clone())
+        .data(state.user_service.clone())
+        .data(state.auth_service.clone())
+        .extension(GraphQLMetrics)
+        .extension(GraphQLErrorCodes)
+        .finish()
+}
+
+/// Registers `/graphql` and `/playground` backed by `schema`.
+pub fn configure(schema: MySchema) -> impl FnOnce(&mut web::ServiceConfig) {
+    move |cfg| {
+        cfg.app_data(web::Data::new(schema))
+            .route("/graphql", web::post().to(graphql_handler))
+            .route("/playground", web::get().to(index_graphiql));
+    }
+}
+
+async fn index_graphiql() -> ActixResult<HttpResponse> {
+    Ok(HttpResponse::Ok()
+        .content_type("text/html; charset=utf-8")
+        .body(
+            async_graphql::http::GraphiQLSource::build()
+                .endpoint("/graphql")
+                .finish()
+        ))
+}
\ No newline at end of file
@@ -1,161 +1,36 @@
-use async_graphql::*;
-use async_graphql_actix_web::{GraphQLRequest, GraphQLResponse};
-use actix_web::{web, App, HttpServer, HttpResponse, Result as ActixResult,
-                middleware::from_fn, HttpRequest, HttpMessage,
-};
+use actix_web::{web, HttpServer, HttpResponse};
 use shared::{
-    database::{
-        apply_migration::MigrationManager,
-        repositories::user_repository::UserRepository,
-        services::user_service::UserService,
-    },
-    models::{
-        user::{
-            GraphQLUser, TimeOffsetDateTime,
-        },
-        mutation::Mutation,
-    },
+    app::{build_app, AppState},
+    config::AppConfig,
+    telemetry,
     error::Error as AppError,
-    auth::{middleware::auth_middleware, CurrentUser, jwt_service::JwtService, auth_service::AuthService,
-    },
 };
-use sqlx::PgPool;
-use std::env;
 use dotenv::dotenv;
 
-type MySchema = Schema<QueryRoot, Mutation, EmptySubscription>;
-
-async fn graphql_handler(
-    schema: web::Data<MySchema>,
-    req: HttpRequest,
-    payload: GraphQLRequest,
-) -> GraphQLResponse {
-    
-    let mut graphql_request = payload.into_inner();
-
-    if let Some(current_user) = req.extensions().get::<CurrentUser>() {
-        graphql_request = graphql_request.data(current_user.clone());
-    }
-
-    let response = schema.execute(graphql_request).await;
-    
-    response.into()
-}
-
-struct QueryRoot;
-
-#[Object]
-impl QueryRoot {
-    async fn hello(&self) -> &str {
-        "Hello, GraphQL!"
-    }
-
-    async fn me(&self, ctx: &Context<'_>) -> Result<GraphQLUser> {
-        let current_user = ctx.data::<CurrentUser>()
-            .map_err(|_| "Not authenticated")?;
-
-        Ok(GraphQLUser {
-            id: current_user.id.into(),
-            username: current_user.username.clone(),
-            email: current_user.email.clone(),
-            created_at: TimeOffsetDateTime(current_user.created_at),
-            updated_at: TimeOffsetDateTime(current_user.updated_at),
-        })
-    }
-
-    async fn user(&self, ctx: &Context<'_>, id: ID) -> Result<Option<GraphQLUser>> {
-        let user_service = ctx.data::<UserService>()?;
-        let id_str = id.0;
-
-        if let Some(user_profile) = user_service.find_by_id(&id_str).await? {
-            Ok(Some(user_profile.into()))
-        } else {
-            Ok(None)
-        }
-    }
-
-    async fn users(&self, ctx: &Context<'_>, ids: Vec<ID>) -> Result<Vec<GraphQLUser>> {
-        let user_service = ctx.data::<UserService>()?;
-        let ids_strs: Vec<&str> = ids.iter().map(|id| id.as_str()).collect();
-
-        let user_profiles = user_service.find_by_ids(&ids_strs).await?;
-        let users: Vec<GraphQLUser> = user_profiles.into_iter().map(|user_profile|user_profile.into()).collect();
-
-        Ok(users)
-    }
-
-    async fn find_all(&self, ctx: &Context<'_>) -> Result<Vec<GraphQLUser>> {
-        let user_service = ctx.data::<UserService>()?;
-
-        let user_profiles = user_service.find_all().await?;
-        let users: Vec<GraphQLUser> = user_profiles.into_iter().map(|user_profile|user_profile.into()).collect();
-
-        Ok(users)
-    }
-}
-
 #[tokio::main]
 async fn main() -> Result<(), AppError> {
-    println!("서버 시작 중...");
-
     dotenv().ok();
 
-    let database_url = env::var("DATABASE_URL")
-        .expect("DATABASE_URL 환경변수가 설정되지 않았습니다");
-
-    println!("데이터베이스 연결 중...");
-    let pool = PgPool::connect(&database_url).await.unwrap();
-
-    println!("마이그레이션 실행 중...");
-    println!("CARGO_MANIFEST_DIR: '{}'", env!("CARGO_MANIFEST_DIR"));
-    let migrations_dir = concat!(env!("CARGO_MANIFEST_DIR"), "/../shared/src/database/migrations");
-    println!("계산된 마이그레이션 경로: '{}'", migrations_dir);
-    let migration_manager = MigrationManager::new(migrations_dir.to_string()).await?;
-
-    MigrationManager::ensure_migration_table(&pool).await?;
-    migration_manager.run_pending_up_migrations(&pool).await?;
-
-    println!("마이그레이션 완료!");
-
-    let user_repo = UserRepository::new(pool.clone());
-    let user_service = UserService::new(user_repo.clone());
-    let auth_service = AuthService::new(user_repo.clone());
-
-    let schema: MySchema = Schema::build(QueryRoot, Mutation::default(), EmptySubscription)
-        .data(pool.clone())
-        .data(user_service.clone())
-        .finish();
-
-    let test_token = JwtService::generate_token("25770f6b-869e-4870-87c8-ecd5c24395e0");
-    println!("Test token: {}", test_token);
+    let config = AppConfig::load_or_exit(8000);
+    telemetry::init(&config.logging);
+    tracing::info!("서버 시작 중...");
+    let bind_address = config.server.bind_address();
+    let state = AppState::from_config(config).await?;
+    tracing::info!(host = %bind_address.0, port = bind_address.1, "요청 대기 중");
+    let schema = graphql::build_schema(&state);
 
     HttpServer::new(move || {
-        App::new()
-            .app_data(web::Data::new(user_service.clone()))
-            .app_data(web::Data::new(auth_service.clone()))
-            .app_data(web::Data::new(schema.clone()))
-            .wrap(from_fn(auth_middleware))
+        build_app(state.clone())
+            .configure(graphql::configure(schema.clone()))
             .route("/", web::get().to(|| async {
                 HttpResponse::Found()
                     .append_header(("Location", "/playground"))
                     .finish()
             }))
-            .route("/graphql", web::post().to(graphql_handler))
-            .route("/playground", web::get().to(index_graphiql))
     })
-    .bind("127.0.0.1:8000")?
+    .bind(bind_address)?
     .run()
     .await?;
 
     Ok(())
 }
-
-async fn index_graphiql() -> ActixResult<HttpResponse> {
-    Ok(HttpResponse::Ok()
-        .content_type("text/html; charset=utf-8")
-        .body(
-            async_graphql::http::GraphiQLSource::build()
-                .endpoint("/graphql")
-                .finish()
-        ))
-}
\ No newline at end of file
@@ -1,3 +1,32 @@
-fn main() {
-    println!("Hello, world!");
+use actix_web::HttpServer;
+use shared::{
+    app::{build_app, AppState},
+    config::AppConfig,
+    telemetry,
+    error::Error as AppError,
+};
+use dotenv::dotenv;
+
+#[tokio::main]
+async fn main() -> Result<(), AppError> {
+    dotenv().ok();
+
+    let config = AppConfig::load_or_exit(8002);
+    telemetry::init(&config.logging);
+    tracing::info!("서버 시작 중...");
+    let bind_address = config.server.bind_address();
+    let state = AppState::from_config(config).await?;
+    tracing::info!(host = %bind_address.0, port = bind_address.1, "요청 대기 중");
+    let schema = graphql::build_schema(&state);
+
+    HttpServer::new(move || {
+        build_app(state.clone())
+            .configure(rest::configure)
+            .configure(graphql::configure(schema.clone()))
+    })
+    .bind(bind_address)?
+    .run()
+    .await?;
+
+    Ok(())
 }
@@ -0,0 +1,184 @@
+use clap::{Parser, Subcommand};
+use dotenv::dotenv;
+use sqlx::PgPool;
+use shared::{
+    app::connect,
+    auth::{PasswordService, Role},
+    config::MigrateConfig,
+    database::{
+        apply_migration::{MigrationManager, MigrationPlan},
+        repositories::user_repository::UserRepository,
+        seed::Seeder,
+        services::user_service::UserService,
+    },
+    error::Error as AppError,
+    telemetry,
+};
+
+/// Applies, rolls back and scaffolds schema migrations using the servers' configuration.
+#[derive(Parser)]
+#[command(name = "migrate")]
+struct Cli {
+    #[command(subcommand)]
+    command: Command,
+}
+
+#[derive(Subcommand)]
+enum Command {
+    /// List applied and pending migrations.
+    Status,
+    /// Apply pending migrations.
+    Up {
+        /// Stop after this version.
+        #[arg(long)]
+        to: Option<i64>,
+        /// Print the plan and its SQL without running anything.
+        #[arg(long)]
+        dry_run: bool,
+    },
+    /// Roll back every migration above a version.
+    Down {
+        /// Version to roll back to; it stays applied.
+        #[arg(long)]
+        to: i64,
+        /// Print the plan and its SQL without running anything.
+        #[arg(long)]
+        dry_run: bool,
+    },
+    /// Roll back the latest migration and apply it again.
+    Redo {
+        /// Print the plan and its SQL without running anything.
+        #[arg(long)]
+        dry_run: bool,
+    },
+    /// Accept edits to applied migrations by re-stamping their checksums.
+    Repair,
+    /// Snapshot the schema at a version so fresh databases skip replaying older migrations.
+    Baseline {
+        /// Version to snapshot; defaults to the latest migration.
+        #[arg(long)]
+        to: Option<i64>,
+    },
+    /// Create the next-numbered `.up.sql`/`.down.sql` pair.
+    New {
+        name: String,
+    },
+    /// Create a user with the Admin role, reading the password from the first line of stdin.
+    CreateAdmin {
+        #[arg(long)]
+        username: String,
+        #[arg(long)]
+        email: String,
+    },
+}
+
+#[tokio::main]
+async fn main() -> Result<(), AppError> {
+    dotenv().ok();
+
+    let cli = Cli::parse();
+    let config = MigrateConfig::load_or_exit();
+    telemetry::init(&config.logging);
+
+    let manager = MigrationManager::new(config.database.migration_source());
+
+    if let Command::New { name } = &cli.command {
+        let (up_path, down_path) = manager.create_migration(name).await?;
+        println!("{}\n{}", up_path.display(), down_path.display());
+        return Ok(());
+    }
+    if let Command::Baseline { to } = &cli.command {
+        let path = manager.create_baseline(&config.database.url, *to).await?;
+        println!("{}", path.display());
+        return Ok(());
+    }
+
+    let pool = connect(&config.database).await?;
+    let lock = MigrationManager::lock(&pool).await?;
+    MigrationManager::ensure_migration_table(&pool).await?;
+
+    match cli.command {
+        Command::Status => {
+            for migration in manager.status(&pool).await? {
+                let state = match (migration.installed_on, migration.has_file) {
+                    _ if migration.failed => "failed".to_string(),
+                    (Some(installed_on), true) => installed_on.format("%Y-%m-%d %H:%M:%S UTC").to_string(),
+                    (Some(_), false) => "applied, file missing".to_string(),
+                    (None, _) => "pending".to_string(),
+                };
+                println!("{:>5}  {:<40} {}", migration.version, migration.description, state);
+            }
+            for drift in manager.find_drift(&pool).await? {
+                println!("drift: {}", drift);
+            }
+        }
+        Command::Up { to, dry_run } => {
+            manager.check_drift(&pool, config.database.on_drift).await?;
+            let plan = manager.plan_up(&pool, to).await?;
+            run(&manager, &pool, &plan, dry_run).await?;
+
+            // Servers skip seeding while migrations are pending, so seed here once the schema is complete.
+            if !dry_run && manager.find_pending_up_migrations(&pool).await?.is_empty() {
+                Seeder::seed_roles_and_permissions(&pool).await?;
+            }
+        }
+        Command::Down { to, dry_run } => {
+            let plan = manager.plan_down(&pool, to).await?;
+            run(&manager, &pool, &plan, dry_run).await?;
+        }
+        Command::Redo { dry_run } => {
+            let plan = manager.plan_redo(&pool).await?;
+            run(&manager, &pool, &plan, dry_run).await?;
+        }
+        Command::Repair => {
+            let repaired = manager.repair(&pool).await?;
+            println!("repaired {} migration(s): {:?}", repaired.len(), repaired);
+        }
+        Command::CreateAdmin { username, email } => {
+            if !manager.find_pending_up_migrations(&pool).await?.is_empty() {
+                return Err(AppError::InvalidInput("Pending migrations; run `migrate up` first".to_string()));
+            }
+            Seeder::seed_roles_and_permissions(&pool).await?;
+
+            let password = read_password()?;
+            let user_service = UserService::new(UserRepository::new(pool.clone()), PasswordService::new(config.password)?);
+            let admin = user_service.create_with_role(&username, &email, &password, Role::Admin).await?;
+            println!("created admin {} ({})", admin.username, admin.id);
+        }
+        Command::New { .. } | Command::Baseline { .. } => unreachable!("handled before connecting"),
+    }
+
+    lock.release().await?;
+
+    Ok(())
+}
+
+/// Reads the password from stdin rather than an argument, which other users could see in the process list.
+fn read_password() -> Result<String, AppError> {
+    let mut password = String::new();
+    std::io::stdin().read_line(&mut password)?;
+
+    let password = password.trim_end_matches(['\r', '\n']).to_string();
+    if password.is_empty() {
+        return Err(AppError::InvalidInput("Pass the admin password on stdin".to_string()));
+    }
+
+    Ok(password)
+}
+
+async fn run(manager: &MigrationManager, pool: &PgPool, plan: &MigrationPlan, dry_run: bool) -> Result<(), AppError> {
+    if !dry_run {
+        return manager.execute_plan(pool, plan).await;
+    }
+
+    if plan.is_empty() {
+        println!("nothing to do");
+    }
+    for step in &plan.steps {
+        let transaction = if MigrationManager::runs_in_transaction(step.sql()) { "" } else { ", no transaction" };
+        println!("-- {} {} ({}{})", step.direction.as_str(), step.migration.version, step.file(), transaction);
+        println!("{}\n", step.sql().trim_end());
+    }
+
+    Ok(())
+}
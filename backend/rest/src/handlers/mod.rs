@@ -1,38 +1,82 @@
-use actix_web::{web, HttpRequest, HttpResponse, Result, HttpMessage};
+use actix_web::{web, HttpResponse, Result};
 use shared::{
-    auth::current_user::CurrentUser,
+    auth::{current_user::CurrentUser, auth_service::AuthService, Claims, Role, RequirePermission, perm},
     models::{user::RestUser,
-            request::CreateUserRequest,
+            request::{CreateUserRequest, LoginRequest, RefreshTokenRequest, LogoutRequest, LogoutAllRequest, DeleteUserQuery, UpdateUserRequest},
     },
     database::services::user_service::UserService,
+    error::Error as AppError,
 };
-use serde_json::json;
+use uuid::Uuid;
 
 pub async fn create_user(
+    _user: RequirePermission<perm::CreateUser>,
     user_data: web::Json<CreateUserRequest>,
     user_service: web::Data<UserService>
 ) -> Result<HttpResponse> {
     let request = user_data.into_inner();
 
-    let password_hash = format!("hashed_{}", request.password); // 임시
-
-    let user_profile = user_service.create(&request.username, &request.email, &password_hash).await?;
+    let user_profile = user_service.create(&request.username, &request.email, &request.password).await?;
 
     let rest_user = RestUser::from(user_profile);
     Ok(HttpResponse::Created().json(rest_user))
 }
 
+pub async fn login(
+    login_data: web::Json<LoginRequest>,
+    auth_service: web::Data<AuthService>
+) -> Result<HttpResponse> {
+    let request = login_data.into_inner();
+
+    let token_pair = auth_service.login(&request.username, &request.password).await?;
+
+    Ok(HttpResponse::Ok().json(token_pair))
+}
+
+pub async fn refresh_token(
+    refresh_data: web::Json<RefreshTokenRequest>,
+    auth_service: web::Data<AuthService>
+) -> Result<HttpResponse> {
+    let request = refresh_data.into_inner();
+
+    let token_pair = auth_service.refresh(&request.refresh_token).await?;
+
+    Ok(HttpResponse::Ok().json(token_pair))
+}
+
+pub async fn logout(
+    claims: Claims,
+    logout_data: Option<web::Json<LogoutRequest>>,
+    auth_service: web::Data<AuthService>
+) -> Result<HttpResponse> {
+    let refresh_token = logout_data.and_then(|data| data.into_inner().refresh_token);
+    auth_service.logout(&claims, refresh_token.as_deref()).await?;
+
+    Ok(HttpResponse::NoContent().finish())
+}
+
+pub async fn logout_all(
+    current_user: CurrentUser,
+    logout_data: Option<web::Json<LogoutAllRequest>>,
+    auth_service: web::Data<AuthService>
+) -> Result<HttpResponse> {
+    let target_id = match logout_data.and_then(|data| data.into_inner().user_id) {
+        Some(id) => Uuid::parse_str(&id)
+            .map_err(|_| AppError::InvalidInput("Invalid user id".to_string()))?,
+        None => current_user.id,
+    };
+
+    auth_service.logout_all(&current_user, target_id).await?;
+
+    Ok(HttpResponse::NoContent().finish())
+}
+
 pub async fn get_me(
-    req: HttpRequest,
+    current_user: CurrentUser,
     user_service: web::Data<UserService>
 ) -> Result<HttpResponse> {
-    let current_user = req.extensions().get::<CurrentUser>()
-        .ok_or_else(|| actix_web::error::ErrorUnauthorized("Not authenticated"))?
-        .clone();
-
-    let user_profile = user_service.find_by_id(&current_user.id.to_string()).await
-        .map_err(|e| actix_web::error::ErrorInternalServerError(e))?
-        .ok_or_else(|| actix_web::error::ErrorNotFound("User not found"))?;
+    let user_profile = user_service.find_by_id(&current_user.id.to_string()).await?
+        .ok_or_else(|| AppError::NotFound("User not found".to_string()))?;
 
     let rest_user = RestUser::from(user_profile);
 
@@ -40,27 +84,76 @@ pub async fn get_me(
 }
 
 pub async fn get_user(
+    _user: RequirePermission<perm::ReadUser>,
     path: web::Path<String>,
     user_service: web::Data<UserService>
 ) -> Result<HttpResponse> {
     let user_id = path.into_inner();
 
-    match user_service.find_by_id(&user_id).await {
-        Ok(Some(profile)) => {
-            let rest_user = RestUser::from(profile);
-            Ok(HttpResponse::Ok().json(rest_user))
-        },
-        Ok(None) => Ok(HttpResponse::NotFound().json(json!({
-            "error": "User not found"
-        }))),
-        Err(e) => Err(actix_web::error::ErrorInternalServerError(e)),
+    let user_profile = user_service.find_by_id(&user_id).await?
+        .ok_or_else(|| AppError::NotFound("User not found".to_string()))?;
+
+    let rest_user = RestUser::from(user_profile);
+
+    Ok(HttpResponse::Ok().json(rest_user))
+}
+
+/// Accepts `application/merge-patch+json` (or plain JSON) with the fields to change.
+pub async fn update_user(
+    current_user: CurrentUser,
+    path: web::Path<String>,
+    user_data: web::Json<UpdateUserRequest>,
+    user_service: web::Data<UserService>
+) -> Result<HttpResponse> {
+    let user_id = path.into_inner();
+    let changes = user_data.into_inner().into_changes()?;
+
+    let user_profile = user_service.update(&current_user, &user_id, changes).await?;
+
+    let rest_user = RestUser::from(user_profile);
+
+    Ok(HttpResponse::Ok().json(rest_user))
+}
+
+pub async fn delete_user(
+    user: RequirePermission<perm::DeleteUser>,
+    path: web::Path<String>,
+    query: web::Query<DeleteUserQuery>,
+    user_service: web::Data<UserService>
+) -> Result<HttpResponse> {
+    let user_id = path.into_inner();
+
+    if query.purge {
+        user_service.purge(&user, &user_id).await?;
+    } else {
+        user_service.soft_delete(&user, &user_id).await?;
     }
+
+    Ok(HttpResponse::NoContent().finish())
 }
 
-pub async fn health_check() -> Result<HttpResponse> {
-    Ok(HttpResponse::Ok().json(json!({
-        "status": "ok",
-        "service": "REST API",
-        "timestamp": chrono::Utc::now()
-    })))
-}
\ No newline at end of file
+pub async fn assign_role(
+    _user: RequirePermission<perm::ManageRoles>,
+    path: web::Path<(String, String)>,
+    user_service: web::Data<UserService>
+) -> Result<HttpResponse> {
+    let (user_id, role_name) = path.into_inner();
+    let role = role_name.parse::<Role>()?;
+
+    user_service.assign_role(&user_id, role).await?;
+
+    Ok(HttpResponse::NoContent().finish())
+}
+
+pub async fn revoke_role(
+    _user: RequirePermission<perm::ManageRoles>,
+    path: web::Path<(String, String)>,
+    user_service: web::Data<UserService>
+) -> Result<HttpResponse> {
+    let (user_id, role_name) = path.into_inner();
+    let role = role_name.parse::<Role>()?;
+
+    user_service.revoke_role(&user_id, role).await?;
+
+    Ok(HttpResponse::NoContent().finish())
+}
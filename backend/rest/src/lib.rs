@@ -0,0 +1,29 @@
+use actix_web::web;
+use shared::{error::Error as AppError, health};
+
+pub mod handlers;
+
+/// Registers the REST API under `/api/v1`.
+pub fn configure(cfg: &mut web::ServiceConfig) {
+    cfg.app_data(web::JsonConfig::default().error_handler(|err, _| {
+        AppError::InvalidInput(err.to_string()).into()
+    }))
+    .app_data(web::QueryConfig::default().error_handler(|err, _| {
+        AppError::InvalidInput(err.to_string()).into()
+    }))
+    .service(
+        web::scope("/api/v1")
+            .route("/auth/login", web::post().to(handlers::login))
+            .route("/auth/refresh", web::post().to(handlers::refresh_token))
+            .route("/auth/logout", web::post().to(handlers::logout))
+            .route("/auth/logout-all", web::post().to(handlers::logout_all))
+            .route("me", web::get().to(handlers::get_me))
+            .route("/users/{id}", web::get().to(handlers::get_user))
+            .route("/users/{id}", web::patch().to(handlers::update_user))
+            .route("/users/{id}", web::delete().to(handlers::delete_user))
+            .route("/users", web::post().to(handlers::create_user))
+            .route("/users/{id}/roles/{role}", web::put().to(handlers::assign_role))
+            .route("/users/{id}/roles/{role}", web::delete().to(handlers::revoke_role))
+            .route("/health", web::get().to(health::live))
+    );
+}
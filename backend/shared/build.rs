@@ -0,0 +1,4 @@
+fn main() {
+    // `include_dir!` doesn't track the directory, so new or edited migrations must force a rebuild.
+    println!("cargo:rerun-if-changed=src/database/migrations");
+}
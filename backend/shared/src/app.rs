@@ -0,0 +1,145 @@
+use crate::{
+    auth::{middleware::auth_middleware, AuthService, JwtService, PasswordService},
+    database::{
+        apply_migration::MigrationManager,
+        repositories::{
+            refresh_token_repository::RefreshTokenRepository,
+            revoked_token_repository::RevokedTokenRepository,
+            user_repository::UserRepository,
+        },
+        seed::Seeder,
+        services::user_service::UserService,
+    },
+    config::{AppConfig, CorsConfig, DatabaseConfig},
+    error::Error,
+    health,
+    metrics::{metrics_handler, metrics_middleware},
+    request_id::{request_id_middleware, REQUEST_ID_HEADER},
+    telemetry::redact_url,
+};
+use actix_cors::Cors;
+use actix_web::{
+    body::MessageBody,
+    dev::{ServiceFactory, ServiceRequest, ServiceResponse},
+    http::header,
+    middleware::from_fn,
+    web, App,
+};
+use sqlx::{postgres::PgPoolOptions, PgPool};
+use std::sync::Arc;
+
+/// Everything a server needs to handle requests. Cheap to clone: one per worker.
+#[derive(Clone)]
+pub struct AppState {
+    pub config: Arc<AppConfig>,
+    pub pool: PgPool,
+    pub user_service: UserService,
+    pub auth_service: AuthService,
+    pub jwt_service: JwtService,
+}
+
+impl AppState {
+    /// Connects to the configured database, checks applied migrations against their files, runs pending
+    /// migrations when `database.auto_migrate` is set and, once none are pending, seeds roles before wiring
+    /// the services.
+    pub async fn from_config(config: AppConfig) -> Result<Self, Error> {
+        tracing::debug!(?config, "설정 로드 완료");
+
+        let database = &config.database;
+        let pool = connect(database).await?;
+
+        let migration_manager = MigrationManager::new(database.migration_source());
+        let lock = MigrationManager::lock(&pool).await?;
+        MigrationManager::ensure_migration_table(&pool).await?;
+        migration_manager.check_drift(&pool, database.on_drift).await?;
+
+        if database.auto_migrate {
+            tracing::info!(source = ?database.migration_source(), "마이그레이션 실행 중");
+            migration_manager.run_pending_up_migrations(&pool).await?;
+        }
+        let pending = migration_manager.find_pending_up_migrations(&pool).await?;
+        lock.release().await?;
+
+        // The seeded tables may not exist yet; start anyway so `/health/ready` can report the pending migrations.
+        if pending.is_empty() {
+            Seeder::seed_roles_and_permissions(&pool).await?;
+        } else {
+            tracing::warn!(?pending, "Pending migrations not applied, skipping role seeding; run `migrate up` or set database.auto_migrate");
+        }
+
+        Self::new(config, pool)
+    }
+
+    pub fn new(config: AppConfig, pool: PgPool) -> Result<Self, Error> {
+        let jwt_service = JwtService::new(config.jwt.clone())?;
+        let password_service = PasswordService::new(config.password)?;
+        let user_repo = UserRepository::new(pool.clone());
+        let refresh_token_repo = RefreshTokenRepository::new(pool.clone());
+        let revoked_token_repo = RevokedTokenRepository::new(pool.clone());
+        let user_service = UserService::new(user_repo.clone(), password_service.clone());
+        let auth_service = AuthService::new(
+            user_repo,
+            refresh_token_repo,
+            revoked_token_repo,
+            jwt_service.clone(),
+            password_service,
+        );
+
+        Ok(Self { config: Arc::new(config), pool, user_service, auth_service, jwt_service })
+    }
+}
+
+/// Opens a connection pool sized and timed out according to `database`.
+pub async fn connect(database: &DatabaseConfig) -> Result<PgPool, Error> {
+    tracing::info!(url = %redact_url(&database.url), "데이터베이스 연결 중");
+    let pool = PgPoolOptions::new()
+        .max_connections(database.max_connections)
+        .min_connections(database.min_connections)
+        .acquire_timeout(database.acquire_timeout)
+        .idle_timeout(database.idle_timeout)
+        .connect(&database.url)
+        .await?;
+
+    Ok(pool)
+}
+
+/// `App` with the shared state, middleware, `/metrics` and `/health/*` in place; callers add their routes with `.configure(..)`.
+pub fn build_app(state: AppState) -> App<
+    impl ServiceFactory<
+        ServiceRequest,
+        Config = (),
+        Response = ServiceResponse<impl MessageBody>,
+        Error = actix_web::Error,
+        InitError = (),
+    >,
+> {
+    let cors = cors(&state.config.cors);
+
+    App::new()
+        .app_data(web::Data::from(state.config))
+        .app_data(web::Data::new(state.pool))
+        .app_data(web::Data::new(state.user_service))
+        .app_data(web::Data::new(state.auth_service))
+        .app_data(web::Data::new(state.jwt_service))
+        .wrap(from_fn(auth_middleware))
+        .wrap(cors)
+        .wrap(from_fn(metrics_middleware))
+        .wrap(from_fn(request_id_middleware))
+        .route("/metrics", web::get().to(metrics_handler))
+        .route("/health/live", web::get().to(health::live))
+        .route("/health/ready", web::get().to(health::ready))
+}
+
+fn cors(config: &CorsConfig) -> Cors {
+    let mut cors = Cors::default()
+        .allowed_methods(["GET", "POST", "PUT", "PATCH", "DELETE"])
+        .allowed_headers([header::AUTHORIZATION, header::CONTENT_TYPE, REQUEST_ID_HEADER])
+        .expose_headers([REQUEST_ID_HEADER])
+        .max_age(config.max_age_secs);
+
+    for origin in &config.allowed_origins {
+        cors = if origin == "*" { cors.allow_any_origin() } else { cors.allowed_origin(origin) };
+    }
+
+    cors
+}
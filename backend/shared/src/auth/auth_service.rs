@@ -1,28 +1,193 @@
 use crate::{
     error::Error,
     auth::{
-        CurrentUser,
+        CurrentUser, Claims, JwtService, PasswordService, Role,
     },
-    database::repositories::user_repository::UserRepository,
+    database::repositories::{
+        user_repository::UserRepository,
+        refresh_token_repository::RefreshTokenRepository,
+        revoked_token_repository::RevokedTokenRepository,
+    },
+    metrics::metrics,
+    models::auth::TokenPair,
 };
+use base64::{engine::general_purpose::URL_SAFE_NO_PAD, Engine};
+use rand::{rngs::OsRng, RngCore};
+use sha2::{Digest, Sha256};
+use time::OffsetDateTime;
+use uuid::Uuid;
+
+const REFRESH_TOKEN_BYTES: usize = 32;
 
 #[derive(Clone)]
 pub struct AuthService {
     user_repo: UserRepository,
+    refresh_token_repo: RefreshTokenRepository,
+    revoked_token_repo: RevokedTokenRepository,
+    jwt_service: JwtService,
+    password_service: PasswordService,
 }
 
 impl AuthService {
-    pub fn new(user_repo: UserRepository) -> Self {
-        Self { user_repo }
+    pub fn new(
+        user_repo: UserRepository,
+        refresh_token_repo: RefreshTokenRepository,
+        revoked_token_repo: RevokedTokenRepository,
+        jwt_service: JwtService,
+        password_service: PasswordService,
+    ) -> Self {
+        Self { user_repo, refresh_token_repo, revoked_token_repo, jwt_service, password_service }
     }
 
     pub async fn create_current_user_by_id(&self, user_id: &str) -> Result<CurrentUser, Error> {
-        let (db_user, role_names) = self.user_repo.find_user_with_roles(&user_id).await
-            .map_err(|e| Error::Database(e))?
+        let user_with_roles = self.user_repo.find_user_with_roles(user_id).await
+            .map_err(Error::Database)?
             .ok_or(Error::NotFound("User not found".to_string()))?;
 
-        let current_user = CurrentUser::from((db_user,role_names));
+        let current_user = CurrentUser::from(user_with_roles);
 
         Ok(current_user)
     }
-}
\ No newline at end of file
+
+    /// Resolves the user behind verified access token claims, rejecting tokens that were logged out
+    /// individually (`jti` denylist) or wholesale (`token_version` bumped by logout-all).
+    pub async fn authenticate(&self, claims: &Claims) -> Result<CurrentUser, Error> {
+        if self.revoked_token_repo.is_revoked(&claims.jti).await? {
+            return Err(Error::Unauthorized("Token revoked".to_string()));
+        }
+
+        let user_with_roles = self.user_repo.find_user_with_roles(&claims.sub).await?
+            .ok_or(Error::NotFound("User not found".to_string()))?;
+
+        if user_with_roles.user.token_version != claims.ver {
+            return Err(Error::Unauthorized("Token revoked".to_string()));
+        }
+
+        Ok(CurrentUser::from(user_with_roles))
+    }
+
+    pub async fn login(&self, username_or_email: &str, password: &str) -> Result<TokenPair, Error> {
+        let invalid_credentials = || {
+            metrics().record_auth_failure("invalid_credentials");
+            Error::Unauthorized("Invalid username or password".to_string())
+        };
+
+        let Some(db_user) = self.user_repo.find_by_username_or_email(username_or_email).await? else {
+            // Spend the same hashing time as a real verification so unknown usernames can't be probed by timing.
+            self.password_service.hash(password).await?;
+            return Err(invalid_credentials());
+        };
+
+        if !self.password_service.verify(password, &db_user.password_hash).await? {
+            return Err(invalid_credentials());
+        }
+
+        if self.password_service.needs_rehash(&db_user.password_hash) {
+            let new_hash = self.password_service.hash(password).await?;
+            if let Err(e) = self.user_repo.update_password_hash(db_user.id, &new_hash).await {
+                tracing::warn!(user_id = %db_user.id, error = %e, "Failed to rehash password");
+            }
+        }
+
+        self.issue_tokens(db_user.id, db_user.token_version, Uuid::new_v4()).await
+    }
+
+    /// Exchanges a refresh token for a new token pair. Every refresh token is single use; presenting one
+    /// that was already rotated revokes its whole family, since either the client or an attacker holds a stolen copy.
+    pub async fn refresh(&self, refresh_token: &str) -> Result<TokenPair, Error> {
+        let token_hash = hash_refresh_token(refresh_token);
+
+        let Some(consumed) = self.refresh_token_repo.consume(&token_hash).await? else {
+            let existing = self.refresh_token_repo.find_by_hash(&token_hash).await?
+                .ok_or_else(|| {
+                    metrics().record_auth_failure("refresh_token_invalid");
+                    Error::Unauthorized("Invalid refresh token".to_string())
+                })?;
+
+            if existing.used_at.is_some() {
+                self.refresh_token_repo.revoke_family(existing.family_id).await?;
+                tracing::warn!(user_id = %existing.user_id, family_id = %existing.family_id, "Refresh token reuse detected, family revoked");
+                metrics().record_auth_failure("refresh_token_reuse");
+                return Err(Error::Unauthorized("Refresh token reuse detected".to_string()));
+            }
+
+            if existing.revoked_at.is_some() {
+                metrics().record_auth_failure("refresh_token_revoked");
+                return Err(Error::Unauthorized("Refresh token revoked".to_string()));
+            }
+
+            metrics().record_auth_failure("refresh_token_expired");
+            return Err(Error::Unauthorized("Refresh token expired".to_string()));
+        };
+
+        let Some(db_user) = self.user_repo.find_by_id(&consumed.user_id.to_string()).await? else {
+            self.refresh_token_repo.revoke_family(consumed.family_id).await?;
+            return Err(Error::Unauthorized("User not found".to_string()));
+        };
+
+        self.issue_tokens(db_user.id, db_user.token_version, consumed.family_id).await
+    }
+
+    /// Ends the session behind `claims`: the access token is denylisted until it expires and, if given,
+    /// the refresh token's family is revoked.
+    pub async fn logout(&self, claims: &Claims, refresh_token: Option<&str>) -> Result<(), Error> {
+        let user_id = Uuid::parse_str(&claims.sub)
+            .map_err(|_| Error::Unauthorized("Malformed token".to_string()))?;
+        let expires_at = OffsetDateTime::from_unix_timestamp(claims.exp)
+            .map_err(|_| Error::Unauthorized("Malformed token".to_string()))?;
+
+        self.revoked_token_repo.revoke(&claims.jti, user_id, expires_at).await?;
+
+        if let Some(refresh_token) = refresh_token
+            && let Some(existing) = self.refresh_token_repo.find_by_hash(&hash_refresh_token(refresh_token)).await?
+            && existing.user_id == user_id
+        {
+            self.refresh_token_repo.revoke_family(existing.family_id).await?;
+        }
+
+        Ok(())
+    }
+
+    /// Invalidates every access and refresh token of `user_id`. Users may do this for themselves;
+    /// forcing it on someone else requires the Admin role.
+    pub async fn logout_all(&self, current_user: &CurrentUser, user_id: Uuid) -> Result<(), Error> {
+        if current_user.id != user_id {
+            current_user.require_role(&Role::Admin)?;
+        }
+
+        self.user_repo.increment_token_version(user_id).await?
+            .ok_or(Error::NotFound("User not found".to_string()))?;
+        self.refresh_token_repo.revoke_all_for_user(user_id).await?;
+
+        Ok(())
+    }
+
+    /// Signs an access token and stores a fresh refresh token in `family_id`.
+    pub async fn issue_tokens(&self, user_id: Uuid, token_version: i32, family_id: Uuid) -> Result<TokenPair, Error> {
+        let access_token = self.jwt_service.generate_token(&user_id.to_string(), token_version)?;
+
+        let refresh_token = generate_refresh_token();
+        let expires_at = OffsetDateTime::now_utc() + self.jwt_service.refresh_token_ttl();
+        self.refresh_token_repo
+            .create(user_id, family_id, &hash_refresh_token(&refresh_token), expires_at)
+            .await?;
+
+        Ok(TokenPair {
+            access_token,
+            refresh_token,
+            token_type: "Bearer".to_string(),
+            expires_in: self.jwt_service.access_token_ttl().whole_seconds(),
+        })
+    }
+}
+
+fn generate_refresh_token() -> String {
+    let mut bytes = [0u8; REFRESH_TOKEN_BYTES];
+    OsRng.fill_bytes(&mut bytes);
+    URL_SAFE_NO_PAD.encode(bytes)
+}
+
+/// Refresh tokens are stored as SHA-256 digests so a database leak doesn't hand out live sessions.
+fn hash_refresh_token(refresh_token: &str) -> Vec<u8> {
+    Sha256::digest(refresh_token.as_bytes()).to_vec()
+}
@@ -3,10 +3,10 @@ use crate::{
     auth::{Role, Permission},
     error::Error,
     database::{
-        models::db_user::DbUser,
+        models::db_user::DbUserWithRoles,
     }
 };
-use std::collections::HashSet;
+use std::{collections::HashSet, hash::Hash, str::FromStr};
 use time::OffsetDateTime;
 
 #[derive(Debug, Clone)]
@@ -21,24 +21,31 @@ pub struct CurrentUser {
 }
 
 impl CurrentUser {
-    fn calculate_permissions(roles: &[Role]) -> Vec<Permission> {
-        let mut permissions = HashSet::new();
-
-        for role in roles {
-            for permission in role.permissions() {
-                permissions.insert(permission);
-            }
-        }
+    /// Parses names read from the database. Names that no longer match an enum variant are
+    /// skipped with a warning rather than failing authentication.
+    fn parse_names<T>(names: Vec<String>, kind: &str) -> Vec<T>
+    where
+        T: FromStr + Eq + Hash,
+    {
+        let parsed: HashSet<T> = names.into_iter()
+            .filter_map(|name| match name.parse::<T>() {
+                Ok(value) => Some(value),
+                Err(_) => {
+                    tracing::warn!(kind, name = %name, "Ignoring unknown name from the database");
+                    None
+                }
+            })
+            .collect();
 
-        permissions.into_iter().collect()
+        parsed.into_iter().collect()
     }
 
     pub fn has_role(&self, role: &Role) -> bool {
-        self.roles.contains(&role)
+        self.roles.contains(role)
     }
 
     pub fn has_permission(&self, permission: &Permission) -> bool {
-        self.permissions.contains(&permission)
+        self.permissions.contains(permission)
     }
 
     pub fn require_role(&self, role: &Role) -> Result<(), Error> {
@@ -62,18 +69,12 @@ impl CurrentUser {
     }
 }
 
-impl From<(DbUser, Vec<String>)> for CurrentUser {
-    fn from((db_user, role_names): (DbUser, Vec<String>)) -> Self {
-        let roles = role_names.into_iter()
-            .filter_map(|role_str| match role_str.as_str() {
-                "Admin" => Some(Role::Admin),
-                "User" => Some(Role::User),
-                "Guest" => Some(Role::Guest),
-                _ => None,
-            })
-            .collect::<Vec<Role>>();
+impl From<DbUserWithRoles> for CurrentUser {
+    fn from(user_with_roles: DbUserWithRoles) -> Self {
+        let DbUserWithRoles { user: db_user, role_names, permission_names } = user_with_roles;
 
-        let permissions = Self::calculate_permissions(&roles);
+        let roles = Self::parse_names::<Role>(role_names, "role");
+        let permissions = Self::parse_names::<Permission>(permission_names, "permission");
 
         CurrentUser {
             id: db_user.id,
@@ -0,0 +1,59 @@
+use crate::{
+    auth::{Claims, CurrentUser},
+    error::Error,
+};
+use actix_web::{dev::Payload, FromRequest, HttpMessage, HttpRequest};
+use std::future::{ready, Ready};
+
+impl CurrentUser {
+    /// The user `auth_middleware` attached to the request, or `Error::Unauthorized` if there is none.
+    pub fn from_http_request(req: &HttpRequest) -> Result<Self, Error> {
+        req.extensions().get::<CurrentUser>()
+            .cloned()
+            .ok_or_else(|| Error::Unauthorized("Not authenticated".to_string()))
+    }
+}
+
+/// Handlers declare `user: CurrentUser` to require authentication; unauthenticated requests get a 401.
+impl FromRequest for CurrentUser {
+    type Error = actix_web::Error;
+    type Future = Ready<Result<Self, Self::Error>>;
+
+    fn from_request(req: &HttpRequest, _: &mut Payload) -> Self::Future {
+        ready(Self::from_http_request(req).map_err(actix_web::Error::from))
+    }
+}
+
+/// Claims of the access token that authenticated the request. 401 when the request is anonymous.
+impl FromRequest for Claims {
+    type Error = actix_web::Error;
+    type Future = Ready<Result<Self, Self::Error>>;
+
+    fn from_request(req: &HttpRequest, _: &mut Payload) -> Self::Future {
+        let claims = req.extensions().get::<Claims>()
+            .cloned()
+            .ok_or_else(|| Error::Unauthorized("Not authenticated".to_string()).into());
+
+        ready(claims)
+    }
+}
+
+/// The current user if the request is authenticated, for routes that serve both anonymous and
+/// signed-in callers. Never rejects the request.
+#[derive(Debug, Clone)]
+pub struct MaybeUser(pub Option<CurrentUser>);
+
+impl MaybeUser {
+    pub fn into_inner(self) -> Option<CurrentUser> {
+        self.0
+    }
+}
+
+impl FromRequest for MaybeUser {
+    type Error = actix_web::Error;
+    type Future = Ready<Result<Self, Self::Error>>;
+
+    fn from_request(req: &HttpRequest, _: &mut Payload) -> Self::Future {
+        ready(Ok(MaybeUser(req.extensions().get::<CurrentUser>().cloned())))
+    }
+}
@@ -0,0 +1,108 @@
+use crate::{
+    auth::{CurrentUser, Permission},
+    error::Error,
+};
+use actix_web::{dev::Payload, FromRequest, HttpRequest};
+use async_graphql::{ErrorExtensions, ResultExt};
+use std::{
+    future::{ready, Ready},
+    marker::PhantomData,
+    ops::Deref,
+};
+
+/// Type-level stand-in for a `Permission`, so routes can name the permission they need in their signature.
+pub trait RequiredPermission {
+    const PERMISSION: Permission;
+}
+
+/// Marker types for `RequirePermission`, one per `Permission` variant.
+pub mod perm {
+    use super::RequiredPermission;
+    use crate::auth::Permission;
+
+    macro_rules! permission_markers {
+        ($($name:ident),* $(,)?) => {
+            $(
+                pub struct $name;
+
+                impl RequiredPermission for $name {
+                    const PERMISSION: Permission = Permission::$name;
+                }
+            )*
+        };
+    }
+
+    permission_markers!(
+        CreateUser,
+        ReadUser,
+        UpdateUser,
+        DeleteUser,
+        ManageRoles,
+        CreatePost,
+        ReadPost,
+        UpdatePost,
+        UpdateOwnPost,
+        DeletePost,
+        DeleteOwnPost,
+        ViewAuditLog,
+        ManageSystem,
+    );
+}
+
+/// Extractor that only succeeds for an authenticated user holding `P::PERMISSION`, e.g.
+/// `user: RequirePermission<perm::ReadUser>`. Rejects with `Error::Unauthorized` without a user
+/// and `Error::Forbidden` without the permission.
+pub struct RequirePermission<P: RequiredPermission> {
+    user: CurrentUser,
+    _permission: PhantomData<P>,
+}
+
+impl<P: RequiredPermission> RequirePermission<P> {
+    pub fn into_inner(self) -> CurrentUser {
+        self.user
+    }
+}
+
+impl<P: RequiredPermission> Deref for RequirePermission<P> {
+    type Target = CurrentUser;
+
+    fn deref(&self) -> &Self::Target {
+        &self.user
+    }
+}
+
+impl<P: RequiredPermission> FromRequest for RequirePermission<P> {
+    type Error = actix_web::Error;
+    type Future = Ready<Result<Self, Self::Error>>;
+
+    fn from_request(req: &HttpRequest, _: &mut Payload) -> Self::Future {
+        let result = CurrentUser::from_http_request(req)
+            .and_then(|user| {
+                user.require_permission(&P::PERMISSION)?;
+                Ok(Self { user, _permission: PhantomData })
+            })
+            .map_err(actix_web::Error::from);
+
+        ready(result)
+    }
+}
+
+/// `async_graphql` field guard: `#[graphql(guard = "PermissionGuard::new(Permission::ReadUser)")]`.
+pub struct PermissionGuard {
+    permission: Permission,
+}
+
+impl PermissionGuard {
+    pub fn new(permission: Permission) -> Self {
+        Self { permission }
+    }
+}
+
+impl async_graphql::Guard for PermissionGuard {
+    async fn check(&self, ctx: &async_graphql::Context<'_>) -> async_graphql::Result<()> {
+        let current_user = ctx.data_opt::<CurrentUser>()
+            .ok_or_else(|| Error::Unauthorized("Not authenticated".to_string()).extend())?;
+
+        current_user.require_permission(&self.permission).extend()
+    }
+}
@@ -1,20 +1,297 @@
 use crate::{
     error::Error,
+    telemetry::Redacted,
 };
+use jsonwebtoken::{
+    decode, encode, errors::ErrorKind, Algorithm, DecodingKey, EncodingKey, Header, Validation,
+};
+use serde::{Serialize, Deserialize};
+use std::fmt;
+use time::{Duration, OffsetDateTime};
+use uuid::Uuid;
+
+pub(crate) const MIN_HMAC_SECRET_LEN: usize = 32;
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Claims {
+    pub sub: String,
+    pub exp: i64,
+    pub iat: i64,
+    pub nbf: i64,
+    pub iss: String,
+    pub aud: String,
+    pub jti: String,
+    /// The user's `token_version` at issue time; bumping it invalidates the token.
+    pub ver: i32,
+}
+
+/// Why a token was rejected. Each reason maps to its own `Error::Unauthorized` message.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TokenError {
+    Expired,
+    NotYetValid,
+    Malformed,
+    InvalidSignature,
+    InvalidIssuer,
+    InvalidAudience,
+}
+
+impl TokenError {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            TokenError::Expired => "expired",
+            TokenError::NotYetValid => "not_yet_valid",
+            TokenError::Malformed => "malformed",
+            TokenError::InvalidSignature => "invalid_signature",
+            TokenError::InvalidIssuer => "invalid_issuer",
+            TokenError::InvalidAudience => "invalid_audience",
+        }
+    }
+}
+
+impl fmt::Display for TokenError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let message = match self {
+            TokenError::Expired => "Token expired",
+            TokenError::NotYetValid => "Token not yet valid",
+            TokenError::Malformed => "Malformed token",
+            TokenError::InvalidSignature => "Invalid token signature",
+            TokenError::InvalidIssuer => "Invalid token issuer",
+            TokenError::InvalidAudience => "Invalid token audience",
+        };
+        f.write_str(message)
+    }
+}
+
+impl From<TokenError> for Error {
+    fn from(err: TokenError) -> Self {
+        Error::Unauthorized(err.to_string())
+    }
+}
+
+impl From<jsonwebtoken::errors::Error> for TokenError {
+    fn from(err: jsonwebtoken::errors::Error) -> Self {
+        match err.kind() {
+            ErrorKind::ExpiredSignature => TokenError::Expired,
+            ErrorKind::ImmatureSignature => TokenError::NotYetValid,
+            ErrorKind::InvalidSignature | ErrorKind::InvalidAlgorithm => TokenError::InvalidSignature,
+            ErrorKind::InvalidIssuer => TokenError::InvalidIssuer,
+            ErrorKind::InvalidAudience => TokenError::InvalidAudience,
+            _ => TokenError::Malformed,
+        }
+    }
+}
 
-pub struct JwtService;
+/// Signing material for access tokens.
+#[derive(Clone)]
+pub enum JwtKeys {
+    /// HS256/HS384/HS512 with a shared secret.
+    Hmac { secret: Vec<u8> },
+    /// RS256/RS384/RS512 with a PEM encoded key pair.
+    Rsa { private_pem: Vec<u8>, public_pem: Vec<u8> },
+}
+
+impl fmt::Debug for JwtKeys {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            JwtKeys::Hmac { secret } => f.debug_struct("Hmac")
+                .field("secret", &Redacted(secret))
+                .finish(),
+            JwtKeys::Rsa { private_pem, public_pem } => f.debug_struct("Rsa")
+                .field("private_pem", &Redacted(private_pem))
+                .field("public_pem", &format_args!("{} bytes", public_pem.len()))
+                .finish(),
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct JwtConfig {
+    pub algorithm: Algorithm,
+    pub keys: JwtKeys,
+    pub issuer: String,
+    pub audience: String,
+    pub access_token_ttl: Duration,
+    /// Lifetime of the opaque refresh tokens issued alongside access tokens.
+    pub refresh_token_ttl: Duration,
+    /// Allowed clock skew, in seconds, when checking `exp` and `nbf`.
+    pub leeway: u64,
+}
+
+#[derive(Clone)]
+pub struct JwtService {
+    encoding_key: EncodingKey,
+    decoding_key: DecodingKey,
+    header: Header,
+    validation: Validation,
+    issuer: String,
+    audience: String,
+    access_token_ttl: Duration,
+    refresh_token_ttl: Duration,
+}
 
 impl JwtService {
-    pub fn generate_token(user_id: &str) -> String {
-        format!("fake_jwt_token_{}", user_id)
+    pub fn new(config: JwtConfig) -> Result<Self, Error> {
+        let (encoding_key, decoding_key) = match &config.keys {
+            JwtKeys::Hmac { secret } => {
+                if secret.len() < MIN_HMAC_SECRET_LEN {
+                    return Err(Error::Server(format!(
+                        "JWT secret must be at least {} bytes", MIN_HMAC_SECRET_LEN
+                    )));
+                }
+                (EncodingKey::from_secret(secret), DecodingKey::from_secret(secret))
+            }
+            JwtKeys::Rsa { private_pem, public_pem } => {
+                let encoding_key = EncodingKey::from_rsa_pem(private_pem)
+                    .map_err(|e| Error::Server(format!("Invalid JWT private key: {}", e)))?;
+                let decoding_key = DecodingKey::from_rsa_pem(public_pem)
+                    .map_err(|e| Error::Server(format!("Invalid JWT public key: {}", e)))?;
+                (encoding_key, decoding_key)
+            }
+        };
+
+        let mut validation = Validation::new(config.algorithm);
+        validation.leeway = config.leeway;
+        validation.validate_nbf = true;
+        validation.set_issuer(&[&config.issuer]);
+        validation.set_audience(&[&config.audience]);
+        validation.set_required_spec_claims(&["sub", "exp", "iat", "nbf", "iss", "aud"]);
+
+        Ok(Self {
+            encoding_key,
+            decoding_key,
+            header: Header::new(config.algorithm),
+            validation,
+            issuer: config.issuer,
+            audience: config.audience,
+            access_token_ttl: config.access_token_ttl,
+            refresh_token_ttl: config.refresh_token_ttl,
+        })
+    }
+
+    pub fn access_token_ttl(&self) -> Duration {
+        self.access_token_ttl
+    }
+
+    pub fn refresh_token_ttl(&self) -> Duration {
+        self.refresh_token_ttl
+    }
+
+    pub fn generate_token(&self, user_id: &str, token_version: i32) -> Result<String, Error> {
+        let now = OffsetDateTime::now_utc();
+
+        let claims = Claims {
+            sub: user_id.to_string(),
+            exp: (now + self.access_token_ttl).unix_timestamp(),
+            iat: now.unix_timestamp(),
+            nbf: now.unix_timestamp(),
+            iss: self.issuer.clone(),
+            aud: self.audience.clone(),
+            jti: Uuid::new_v4().to_string(),
+            ver: token_version,
+        };
+
+        encode(&self.header, &claims, &self.encoding_key)
+            .map_err(|e| Error::Server(format!("Failed to sign token: {}", e)))
+    }
+
+    pub fn verify_token(&self, token: &str) -> Result<Claims, TokenError> {
+        let data = decode::<Claims>(token, &self.decoding_key, &self.validation)?;
+
+        Ok(data.claims)
     }
+}
 
-    pub fn verify_token(token: &str) -> Result<String, Error> {
-        if token.starts_with("fake_jwt_token_") {
-            let user_id = token.replace("fake_jwt_token_", "");
-            Ok(user_id)
-        } else {
-            Err(Error::Unauthorized("Invalid token".to_string()))
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn config() -> JwtConfig {
+        JwtConfig {
+            algorithm: Algorithm::HS256,
+            keys: JwtKeys::Hmac { secret: b"0123456789abcdef0123456789abcdef".to_vec() },
+            issuer: "test-issuer".to_string(),
+            audience: "test-audience".to_string(),
+            access_token_ttl: Duration::minutes(15),
+            refresh_token_ttl: Duration::days(1),
+            leeway: 0,
         }
     }
-}
\ No newline at end of file
+
+    fn service(config: JwtConfig) -> JwtService {
+        JwtService::new(config).unwrap()
+    }
+
+    #[test]
+    fn round_trip() {
+        let jwt = service(config());
+        let token = jwt.generate_token("user-1", 3).unwrap();
+
+        let claims = jwt.verify_token(&token).unwrap();
+        assert_eq!(claims.sub, "user-1");
+        assert_eq!(claims.ver, 3);
+        assert_eq!(claims.iss, "test-issuer");
+        assert_eq!(claims.aud, "test-audience");
+    }
+
+    #[test]
+    fn rejects_expired_token() {
+        let jwt = service(JwtConfig { access_token_ttl: Duration::minutes(-5), ..config() });
+        let token = jwt.generate_token("user-1", 0).unwrap();
+
+        assert_eq!(jwt.verify_token(&token).unwrap_err(), TokenError::Expired);
+    }
+
+    #[test]
+    fn rejects_token_not_yet_valid() {
+        let jwt = service(config());
+        let now = OffsetDateTime::now_utc();
+        let claims = Claims {
+            sub: "user-1".to_string(),
+            exp: (now + Duration::hours(2)).unix_timestamp(),
+            iat: now.unix_timestamp(),
+            nbf: (now + Duration::hours(1)).unix_timestamp(),
+            iss: "test-issuer".to_string(),
+            aud: "test-audience".to_string(),
+            jti: Uuid::new_v4().to_string(),
+            ver: 0,
+        };
+        let token = encode(&jwt.header, &claims, &jwt.encoding_key).unwrap();
+
+        assert_eq!(jwt.verify_token(&token).unwrap_err(), TokenError::NotYetValid);
+    }
+
+    #[test]
+    fn rejects_malformed_token() {
+        let jwt = service(config());
+
+        assert_eq!(jwt.verify_token("not-a-jwt").unwrap_err(), TokenError::Malformed);
+    }
+
+    #[test]
+    fn rejects_token_signed_with_another_secret() {
+        let other = service(JwtConfig {
+            keys: JwtKeys::Hmac { secret: b"fedcba9876543210fedcba9876543210".to_vec() },
+            ..config()
+        });
+        let token = other.generate_token("user-1", 0).unwrap();
+
+        assert_eq!(service(config()).verify_token(&token).unwrap_err(), TokenError::InvalidSignature);
+    }
+
+    #[test]
+    fn rejects_token_from_another_issuer() {
+        let other = service(JwtConfig { issuer: "other-issuer".to_string(), ..config() });
+        let token = other.generate_token("user-1", 0).unwrap();
+
+        assert_eq!(service(config()).verify_token(&token).unwrap_err(), TokenError::InvalidIssuer);
+    }
+
+    #[test]
+    fn rejects_token_for_another_audience() {
+        let other = service(JwtConfig { audience: "other-audience".to_string(), ..config() });
+        let token = other.generate_token("user-1", 0).unwrap();
+
+        assert_eq!(service(config()).verify_token(&token).unwrap_err(), TokenError::InvalidAudience);
+    }
+}
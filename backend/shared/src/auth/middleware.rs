@@ -1,51 +1,64 @@
 use crate::{
     auth::{JwtService, AuthService},
+    error::Error as AppError,
+    metrics::metrics,
+    telemetry::Redacted,
 };
 use actix_web::{
     middleware::Next,
     dev::{ServiceRequest, ServiceResponse},
     Error as ActixError,
     HttpMessage,
-    body::MessageBody,
+    body::{EitherBody, MessageBody},
     web,
 };
 
 pub async fn auth_middleware(
     req: ServiceRequest,
     next: Next<impl MessageBody>,
-) -> Result<ServiceResponse<impl MessageBody>, ActixError> {
-
-    println!("[DEBUG] Auth middleware called for: {}", req.path());
-    if let Some(auth_header) = req.headers().get("Authorization") {
-        println!("[DEBUG] Authorization header: {:?}", auth_header);
-        if let Ok(auth_str) = auth_header.to_str() {
-            if let Some(token) = auth_str.strip_prefix("Bearer ") {
-                println!("추출된 토큰: {}", token);
-
-                match JwtService::verify_token(token) {
-                    Ok(user_id) => {
-                        println!("추출된 user_id: {}", user_id);
-
-                        if let Some(auth_service) = req.app_data::<web::Data<AuthService>>() {
-                            match auth_service.create_current_user_by_id(&user_id).await {
-                                Ok(current_user) => {
-                                    req.extensions_mut().insert(current_user);
-                                }
-                                Err(_) => {
-                                    return Err(actix_web::error::ErrorUnauthorized("User not found"));
-                                }
-                            }
-                        } else {
-                            return Err(actix_web::error::ErrorInternalServerError("UserRepository not found"));
-                        }
-                    }
-                    Err(_) => {
-                        return Err(actix_web::error::ErrorUnauthorized("Invalid token"))
-                    }
-                }
+) -> Result<ServiceResponse<EitherBody<impl MessageBody>>, ActixError> {
+
+    // 에러는 여기서 응답으로 변환해야 request id가 붙는다
+    if let Err(e) = authenticate_request(&req).await {
+        return Ok(req.error_response(e).map_into_right_body());
+    }
+
+    next.call(req).await.map(ServiceResponse::map_into_left_body)
+}
+
+async fn authenticate_request(req: &ServiceRequest) -> Result<(), AppError> {
+    if let Some(auth_header) = req.headers().get("Authorization")
+        && let Ok(auth_str) = auth_header.to_str()
+        && let Some(token) = auth_str.strip_prefix("Bearer ")
+    {
+        let jwt_service = req.app_data::<web::Data<JwtService>>()
+            .ok_or_else(|| AppError::Server("JwtService not found".to_string()))?;
+
+        let claims = jwt_service.verify_token(token).inspect_err(|reason| {
+            metrics().record_auth_failure(reason.as_str());
+            tracing::debug!(%reason, token = %Redacted(token.as_bytes()), "토큰 검증 실패");
+        })?;
+        tracing::debug!(user_id = %claims.sub, "토큰 검증 성공");
+
+        let auth_service = req.app_data::<web::Data<AuthService>>()
+            .ok_or_else(|| AppError::Server("AuthService not found".to_string()))?;
+
+        let current_user = match auth_service.authenticate(&claims).await {
+            Ok(current_user) => current_user,
+            Err(AppError::NotFound(_)) => {
+                metrics().record_auth_failure("user_not_found");
+                return Err(AppError::Unauthorized("User not found".to_string()));
             }
-        }
+            Err(e @ AppError::Unauthorized(_)) => {
+                metrics().record_auth_failure("token_revoked");
+                return Err(e);
+            }
+            Err(e) => return Err(e),
+        };
+
+        req.extensions_mut().insert(current_user);
+        req.extensions_mut().insert(claims);
     }
 
-    next.call(req).await
-}
\ No newline at end of file
+    Ok(())
+}
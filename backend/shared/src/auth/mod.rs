@@ -4,9 +4,15 @@ pub mod permission;
 pub mod role;
 pub mod jwt_service;
 pub mod auth_service;
+pub mod password_service;
+pub mod guard;
+pub mod extractor;
 
 pub use current_user::CurrentUser;
 pub use role::Role;
 pub use permission::Permission;
-pub use jwt_service::JwtService;
-pub use auth_service::AuthService;
\ No newline at end of file
+pub use jwt_service::{JwtService, Claims, TokenError};
+pub use auth_service::AuthService;
+pub use password_service::{PasswordService, PasswordConfig};
+pub use guard::{RequirePermission, PermissionGuard, perm};
+pub use extractor::MaybeUser;
\ No newline at end of file
@@ -0,0 +1,94 @@
+use crate::{
+    error::Error,
+};
+use argon2::{
+    password_hash::{rand_core::OsRng, PasswordHash, PasswordHasher, PasswordVerifier, SaltString},
+    Algorithm, Argon2, Params, Version,
+};
+
+/// Argon2id cost parameters. Defaults follow the OWASP recommendation (19 MiB, 2 passes, 1 lane).
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct PasswordConfig {
+    pub memory_kib: u32,
+    pub iterations: u32,
+    pub parallelism: u32,
+}
+
+impl Default for PasswordConfig {
+    fn default() -> Self {
+        Self {
+            memory_kib: 19 * 1024,
+            iterations: 2,
+            parallelism: 1,
+        }
+    }
+}
+
+#[derive(Clone)]
+pub struct PasswordService {
+    argon2: Argon2<'static>,
+    params: Params,
+}
+
+impl PasswordService {
+    pub fn new(config: PasswordConfig) -> Result<Self, Error> {
+        let params = Params::new(config.memory_kib, config.iterations, config.parallelism, None)
+            .map_err(|e| Error::Server(format!("Invalid Argon2 parameters: {}", e)))?;
+
+        Ok(Self {
+            argon2: Argon2::new(Algorithm::Argon2id, Version::V0x13, params.clone()),
+            params,
+        })
+    }
+
+    /// Hashes `password` into a PHC string. Runs on the blocking pool since Argon2 is CPU bound.
+    pub async fn hash(&self, password: &str) -> Result<String, Error> {
+        let argon2 = self.argon2.clone();
+        let password = password.to_string();
+
+        tokio::task::spawn_blocking(move || {
+            let salt = SaltString::generate(&mut OsRng);
+            argon2.hash_password(password.as_bytes(), &salt)
+                .map(|hash| hash.to_string())
+                .map_err(|e| Error::Server(format!("Failed to hash password: {}", e)))
+        })
+        .await
+        .map_err(|e| Error::Server(format!("Password hashing task failed: {}", e)))?
+    }
+
+    /// Returns `false` for a wrong password and for hashes that are not valid PHC strings.
+    pub async fn verify(&self, password: &str, password_hash: &str) -> Result<bool, Error> {
+        let argon2 = self.argon2.clone();
+        let password = password.to_string();
+        let password_hash = password_hash.to_string();
+
+        tokio::task::spawn_blocking(move || {
+            match PasswordHash::new(&password_hash) {
+                Ok(parsed) => argon2.verify_password(password.as_bytes(), &parsed).is_ok(),
+                Err(_) => false,
+            }
+        })
+        .await
+        .map_err(|e| Error::Server(format!("Password verification task failed: {}", e)))
+    }
+
+    /// Whether `password_hash` was produced with a different algorithm, version or cost than configured.
+    pub fn needs_rehash(&self, password_hash: &str) -> bool {
+        let Ok(parsed) = PasswordHash::new(password_hash) else {
+            return true;
+        };
+
+        if parsed.algorithm != Algorithm::Argon2id.ident() || parsed.version != Some(Version::V0x13.into()) {
+            return true;
+        }
+
+        match Params::try_from(&parsed) {
+            Ok(params) => {
+                params.m_cost() != self.params.m_cost()
+                    || params.t_cost() != self.params.t_cost()
+                    || params.p_cost() != self.params.p_cost()
+            }
+            Err(_) => true,
+        }
+    }
+}
@@ -1,4 +1,6 @@
+use crate::error::Error;
 use serde::{Serialize, Deserialize};
+use std::{fmt, str::FromStr};
 
 #[derive(Debug, Clone, PartialEq, Eq, Hash, Serialize, Deserialize)]
 pub enum Permission {
@@ -23,6 +25,41 @@ pub enum Permission {
 }
 
 impl Permission {
+    pub const ALL: [Permission; 13] = [
+        Permission::CreateUser,
+        Permission::ReadUser,
+        Permission::UpdateUser,
+        Permission::DeleteUser,
+        Permission::ManageRoles,
+        Permission::CreatePost,
+        Permission::ReadPost,
+        Permission::UpdatePost,
+        Permission::UpdateOwnPost,
+        Permission::DeletePost,
+        Permission::DeleteOwnPost,
+        Permission::ViewAuditLog,
+        Permission::ManageSystem,
+    ];
+
+    /// The name stored in `permissions.name`.
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Permission::CreateUser => "CreateUser",
+            Permission::ReadUser => "ReadUser",
+            Permission::UpdateUser => "UpdateUser",
+            Permission::DeleteUser => "DeleteUser",
+            Permission::ManageRoles => "ManageRoles",
+            Permission::CreatePost => "CreatePost",
+            Permission::ReadPost => "ReadPost",
+            Permission::UpdatePost => "UpdatePost",
+            Permission::UpdateOwnPost => "UpdateOwnPost",
+            Permission::DeletePost => "DeletePost",
+            Permission::DeleteOwnPost => "DeleteOwnPost",
+            Permission::ViewAuditLog => "ViewAuditLog",
+            Permission::ManageSystem => "ManageSystem",
+        }
+    }
+
     pub fn description(&self) -> &'static str {
         match self {
             Permission::CreateUser => "Create new users",
@@ -37,7 +74,36 @@ impl Permission {
             Permission::DeletePost => "Delete any post",
             Permission::DeleteOwnPost => "Delete own posts only",
             Permission::ViewAuditLog => "View system audit logs",
-            Permission::ManageSystem => "Mange system settings",
+            Permission::ManageSystem => "Manage system settings",
+        }
+    }
+}
+
+impl fmt::Display for Permission {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+impl FromStr for Permission {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "CreateUser" => Ok(Permission::CreateUser),
+            "ReadUser" => Ok(Permission::ReadUser),
+            "UpdateUser" => Ok(Permission::UpdateUser),
+            "DeleteUser" => Ok(Permission::DeleteUser),
+            "ManageRoles" => Ok(Permission::ManageRoles),
+            "CreatePost" => Ok(Permission::CreatePost),
+            "ReadPost" => Ok(Permission::ReadPost),
+            "UpdatePost" => Ok(Permission::UpdatePost),
+            "UpdateOwnPost" => Ok(Permission::UpdateOwnPost),
+            "DeletePost" => Ok(Permission::DeletePost),
+            "DeleteOwnPost" => Ok(Permission::DeleteOwnPost),
+            "ViewAuditLog" => Ok(Permission::ViewAuditLog),
+            "ManageSystem" => Ok(Permission::ManageSystem),
+            _ => Err(Error::InvalidInput(format!("Unknown permission: {}", s))),
         }
     }
-}
\ No newline at end of file
+}
@@ -1,7 +1,12 @@
-use crate::auth::Permission;
+use crate::{
+    auth::Permission,
+    error::Error,
+};
+use async_graphql::Enum;
 use serde::{Serialize, Deserialize};
+use std::{fmt, str::FromStr};
 
-#[derive(Debug, Clone, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize, Enum)]
 pub enum Role {
     Admin,
     Moderator,
@@ -10,7 +15,30 @@ pub enum Role {
 }
 
 impl Role {
-    pub fn permissions(&self) -> Vec<Permission> {
+    pub const ALL: [Role; 4] = [Role::Admin, Role::Moderator, Role::User, Role::Guest];
+
+    /// The name stored in `roles.name`.
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Role::Admin => "Admin",
+            Role::Moderator => "Moderator",
+            Role::User => "User",
+            Role::Guest => "Guest",
+        }
+    }
+
+    pub fn description(&self) -> &'static str {
+        match self {
+            Role::Admin => "Manage users, roles and system settings",
+            Role::Moderator => "Moderate users and posts",
+            Role::User => "Regular member",
+            Role::Guest => "Read-only access",
+        }
+    }
+
+    /// Grants a freshly created role starts with. Effective permissions are read from
+    /// `role_permissions`, so operators can change them without recompiling.
+    pub fn default_permissions(&self) -> Vec<Permission> {
         match self {
             Role::Admin => vec![
                 Permission::CreateUser,
@@ -35,4 +63,24 @@ impl Role {
             ],
         }
     }
-}
\ No newline at end of file
+}
+
+impl fmt::Display for Role {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+impl FromStr for Role {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "Admin" => Ok(Role::Admin),
+            "Moderator" => Ok(Role::Moderator),
+            "User" => Ok(Role::User),
+            "Guest" => Ok(Role::Guest),
+            _ => Err(Error::InvalidInput(format!("Unknown role: {}", s))),
+        }
+    }
+}
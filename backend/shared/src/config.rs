@@ -0,0 +1,609 @@
+use crate::{
+    auth::{
+        jwt_service::{JwtConfig, JwtKeys, MIN_HMAC_SECRET_LEN},
+        PasswordConfig,
+    },
+    database::{apply_migration::DriftPolicy, migration_source::MigrationSource},
+    telemetry::redact_url,
+};
+use jsonwebtoken::Algorithm;
+use serde::de::DeserializeOwned;
+use std::{collections::BTreeMap, env, fmt, fs, path::Path, str::FromStr, time::Duration};
+use thiserror::Error;
+
+const ENV_PREFIX: &str = "APP_";
+const ENV_SEPARATOR: &str = "__";
+/// Points at the TOML file to load. Without it `config.toml` in the working directory is used if present.
+const CONFIG_FILE_VAR: &str = "APP_CONFIG_FILE";
+const DEFAULT_CONFIG_FILE: &str = "config.toml";
+/// Top-level tables `AppConfig` reads. Leftover env vars outside them (`APP_ENV`, `APP_NAME`, ...)
+/// belong to something else and are ignored rather than reported.
+const SECTIONS: &[&str] = &["server", "database", "jwt", "password", "cors", "logging"];
+/// The `jwt.secret` that earlier versions of `config.example.toml` shipped with; it is public, so it is refused.
+const EXAMPLE_JWT_SECRET: &str = "change-me-to-at-least-32-bytes-of-random";
+/// Sections `MigrateConfig` reads, and so checks for unknown keys.
+const MIGRATE_SECTIONS: &[&str] = &["database", "password", "logging"];
+
+/// Settings shared by every binary, layered as built-in defaults < TOML file < `APP_` env vars.
+/// Env keys map onto the file's sections with `__`, e.g. `APP_DATABASE__MAX_CONNECTIONS`.
+#[derive(Debug, Clone)]
+pub struct AppConfig {
+    pub server: ServerConfig,
+    pub database: DatabaseConfig,
+    pub jwt: JwtConfig,
+    pub password: PasswordConfig,
+    pub cors: CorsConfig,
+    pub logging: LoggingConfig,
+}
+
+/// The part of the configuration the `migrate` binary reads. Server, JWT and CORS settings in the same
+/// file are ignored rather than validated, so migrating doesn't need the servers' secrets. `password` is
+/// read so `create-admin` hashes with the same Argon2 parameters as the servers.
+#[derive(Debug, Clone)]
+pub struct MigrateConfig {
+    pub database: DatabaseConfig,
+    pub password: PasswordConfig,
+    pub logging: LoggingConfig,
+}
+
+#[derive(Debug, Clone)]
+pub struct ServerConfig {
+    pub host: String,
+    pub port: u16,
+}
+
+impl ServerConfig {
+    pub fn bind_address(&self) -> (String, u16) {
+        (self.host.clone(), self.port)
+    }
+}
+
+#[derive(Clone)]
+pub struct DatabaseConfig {
+    pub url: String,
+    pub max_connections: u32,
+    pub min_connections: u32,
+    pub acquire_timeout: Duration,
+    /// `None` keeps idle connections open indefinitely.
+    pub idle_timeout: Option<Duration>,
+    /// Read migrations from this directory instead of the copies embedded at compile time.
+    pub migrations_dir: Option<String>,
+    /// Apply pending migrations on server start. Off by default; use the `migrate` binary instead.
+    pub auto_migrate: bool,
+    /// Whether an applied migration whose file changed or vanished stops startup or only logs a warning.
+    pub on_drift: DriftPolicy,
+}
+
+impl DatabaseConfig {
+    pub fn migration_source(&self) -> MigrationSource {
+        match &self.migrations_dir {
+            Some(dir) => MigrationSource::Filesystem(dir.into()),
+            None => MigrationSource::Embedded,
+        }
+    }
+}
+
+impl fmt::Debug for DatabaseConfig {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("DatabaseConfig")
+            .field("url", &redact_url(&self.url))
+            .field("max_connections", &self.max_connections)
+            .field("min_connections", &self.min_connections)
+            .field("acquire_timeout", &self.acquire_timeout)
+            .field("idle_timeout", &self.idle_timeout)
+            .field("migrations_dir", &self.migrations_dir)
+            .field("auto_migrate", &self.auto_migrate)
+            .field("on_drift", &self.on_drift)
+            .finish()
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct CorsConfig {
+    /// Exact origins such as `https://app.example.com`, or `*` for any. Empty disables cross-origin access.
+    pub allowed_origins: Vec<String>,
+    pub max_age_secs: usize,
+}
+
+#[derive(Debug, Clone)]
+pub struct LoggingConfig {
+    /// Filter directive, e.g. `info` or `info,sqlx=warn`.
+    pub level: String,
+    pub format: LogFormat,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum LogFormat {
+    Pretty,
+    Json,
+}
+
+impl FromStr for LogFormat {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "pretty" => Ok(LogFormat::Pretty),
+            "json" => Ok(LogFormat::Json),
+            _ => Err(format!("must be \"pretty\" or \"json\", got \"{}\"", s)),
+        }
+    }
+}
+
+/// Every problem found while loading, so a broken deployment can be fixed in one go.
+#[derive(Debug, Error)]
+pub struct ConfigError {
+    pub errors: Vec<InvalidKey>,
+}
+
+#[derive(Debug)]
+pub struct InvalidKey {
+    pub key: String,
+    /// Where the offending value came from: a file path, an env var or `default`.
+    pub source: String,
+    pub message: String,
+}
+
+impl fmt::Display for ConfigError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "Invalid configuration ({} problem(s)):", self.errors.len())?;
+        for error in &self.errors {
+            write!(f, "\n  - {} [{}]: {}", error.key, error.source, error.message)?;
+        }
+        Ok(())
+    }
+}
+
+impl AppConfig {
+    /// `default_port` lets each binary keep its own port when none is configured.
+    pub fn load(default_port: u16) -> Result<Self, ConfigError> {
+        let mut reader = Reader::from_sources();
+        let config = Self::read(&mut reader, default_port);
+        reader.finish(SECTIONS).map(|_| config)
+    }
+
+    /// Like `load`, but prints the problems and exits instead of returning them.
+    pub fn load_or_exit(default_port: u16) -> Self {
+        or_exit(Self::load(default_port))
+    }
+
+    fn read(reader: &mut Reader, default_port: u16) -> Self {
+        let server = ServerConfig {
+            host: reader.get("server.host", "127.0.0.1".to_string()),
+            port: reader.get("server.port", default_port),
+        };
+        if server.host.is_empty() {
+            reader.invalid("server.host", "must not be empty");
+        }
+
+        let database = Self::read_database(reader);
+        let jwt = Self::read_jwt(reader);
+        let password = Self::read_password(reader);
+
+        let cors = CorsConfig {
+            allowed_origins: reader.list("cors.allowed_origins"),
+            max_age_secs: reader.get("cors.max_age_secs", 3600),
+        };
+        for origin in &cors.allowed_origins {
+            if origin != "*" && !origin.starts_with("http://") && !origin.starts_with("https://") {
+                reader.invalid("cors.allowed_origins", &format!("\"{}\" is not an http(s) origin or \"*\"", origin));
+            }
+        }
+
+        let logging = Self::read_logging(reader);
+
+        Self { server, database, jwt, password, cors, logging }
+    }
+
+    fn read_password(reader: &mut Reader) -> PasswordConfig {
+        let defaults = PasswordConfig::default();
+        let password = PasswordConfig {
+            memory_kib: reader.get("password.memory_kib", defaults.memory_kib),
+            iterations: reader.get("password.iterations", defaults.iterations),
+            parallelism: reader.get("password.parallelism", defaults.parallelism),
+        };
+        if let Err(e) = argon2::Params::new(password.memory_kib, password.iterations, password.parallelism, None) {
+            reader.invalid("password", &format!("invalid Argon2 parameters: {}", e));
+        }
+
+        password
+    }
+
+    fn read_logging(reader: &mut Reader) -> LoggingConfig {
+        let logging = LoggingConfig {
+            level: reader.get("logging.level", "info".to_string()),
+            format: reader.parse("logging.format", LogFormat::Pretty),
+        };
+        if let Err(e) = tracing_subscriber::EnvFilter::try_new(&logging.level) {
+            reader.invalid("logging.level", &format!("invalid filter directive: {}", e));
+        }
+
+        logging
+    }
+
+    fn read_database(reader: &mut Reader) -> DatabaseConfig {
+        let url = reader.optional::<String>("database.url").unwrap_or_else(|| {
+            reader.invalid("database.url", "is required (set APP_DATABASE__URL or DATABASE_URL)");
+            String::new()
+        });
+
+        let database = DatabaseConfig {
+            url,
+            max_connections: reader.get("database.max_connections", 10),
+            min_connections: reader.get("database.min_connections", 0),
+            acquire_timeout: Duration::from_secs(reader.get("database.acquire_timeout_secs", 30)),
+            idle_timeout: match reader.get::<u64>("database.idle_timeout_secs", 600) {
+                0 => None,
+                secs => Some(Duration::from_secs(secs)),
+            },
+            migrations_dir: reader.optional("database.migrations_dir"),
+            auto_migrate: reader.get("database.auto_migrate", false),
+            on_drift: reader.parse("database.on_drift", DriftPolicy::Error),
+        };
+
+        if database.max_connections == 0 {
+            reader.invalid("database.max_connections", "must be at least 1");
+        }
+        if database.min_connections > database.max_connections {
+            reader.invalid("database.min_connections", "must not exceed database.max_connections");
+        }
+
+        database
+    }
+
+    fn read_jwt(reader: &mut Reader) -> JwtConfig {
+        let algorithm = reader.parse_with("jwt.algorithm", Algorithm::HS256, |value| {
+            Algorithm::from_str(value).map_err(|_| format!("unsupported algorithm \"{}\"", value))
+        });
+
+        let keys = match algorithm {
+            Algorithm::RS256 | Algorithm::RS384 | Algorithm::RS512 => JwtKeys::Rsa {
+                private_pem: reader.key_file("jwt.private_key_path"),
+                public_pem: reader.key_file("jwt.public_key_path"),
+            },
+            Algorithm::HS256 | Algorithm::HS384 | Algorithm::HS512 => {
+                let secret = reader.optional::<String>("jwt.secret").unwrap_or_default();
+                if secret.len() < MIN_HMAC_SECRET_LEN {
+                    reader.invalid("jwt.secret", &format!("must be at least {} bytes for {:?}", MIN_HMAC_SECRET_LEN, algorithm));
+                } else if secret == EXAMPLE_JWT_SECRET {
+                    reader.invalid("jwt.secret", "is the published example value; generate a random key");
+                }
+                JwtKeys::Hmac { secret: secret.into_bytes() }
+            }
+            other => {
+                reader.invalid("jwt.algorithm", &format!("{:?} is not supported", other));
+                JwtKeys::Hmac { secret: Vec::new() }
+            }
+        };
+
+        let access_token_ttl = reader.get::<i64>("jwt.access_token_ttl_secs", 15 * 60);
+        let refresh_token_ttl = reader.get::<i64>("jwt.refresh_token_ttl_secs", 14 * 24 * 60 * 60);
+        for (key, ttl) in [("jwt.access_token_ttl_secs", access_token_ttl), ("jwt.refresh_token_ttl_secs", refresh_token_ttl)] {
+            if ttl <= 0 {
+                reader.invalid(key, "must be positive");
+            }
+        }
+
+        JwtConfig {
+            algorithm,
+            keys,
+            issuer: reader.get("jwt.issuer", "rust-actix-web-playground".to_string()),
+            audience: reader.get("jwt.audience", "rust-actix-web-playground".to_string()),
+            access_token_ttl: time::Duration::seconds(access_token_ttl),
+            refresh_token_ttl: time::Duration::seconds(refresh_token_ttl),
+            leeway: reader.get("jwt.leeway_secs", 30),
+        }
+    }
+}
+
+impl MigrateConfig {
+    /// Reads the same file and env vars as `AppConfig::load`, but only the `database`, `password` and `logging` sections.
+    pub fn load() -> Result<Self, ConfigError> {
+        let mut reader = Reader::from_sources();
+        let config = Self {
+            database: AppConfig::read_database(&mut reader),
+            password: AppConfig::read_password(&mut reader),
+            logging: AppConfig::read_logging(&mut reader),
+        };
+        reader.finish(MIGRATE_SECTIONS).map(|_| config)
+    }
+
+    /// Like `load`, but prints the problems and exits instead of returning them.
+    pub fn load_or_exit() -> Self {
+        or_exit(Self::load())
+    }
+}
+
+fn or_exit<T>(result: Result<T, ConfigError>) -> T {
+    result.unwrap_or_else(|err| {
+        eprintln!("{}", err);
+        std::process::exit(1);
+    })
+}
+
+/// Merged file and env values keyed by dotted path (`database.url`), plus the errors found reading them.
+#[derive(Default)]
+struct Reader {
+    values: BTreeMap<String, (toml::Value, String)>,
+    sources: BTreeMap<String, String>,
+    errors: Vec<InvalidKey>,
+}
+
+impl Reader {
+    /// The file named by `APP_CONFIG_FILE` (or `config.toml` if present), overridden by the environment.
+    fn from_sources() -> Self {
+        let mut reader = Reader::default();
+
+        let (path, required) = match env::var(CONFIG_FILE_VAR) {
+            Ok(path) => (path, true),
+            Err(_) => (DEFAULT_CONFIG_FILE.to_string(), false),
+        };
+        if required || Path::new(&path).exists() {
+            reader.add_file(&path);
+        }
+        reader.add_env(env::vars());
+
+        reader
+    }
+
+    fn add_file(&mut self, path: &str) {
+        let table = fs::read_to_string(path)
+            .map_err(|e| e.to_string())
+            .and_then(|contents| contents.parse::<toml::Table>().map_err(|e| e.to_string()));
+
+        match table {
+            Ok(table) => self.add_table(String::new(), table, path),
+            Err(message) => self.errors.push(InvalidKey {
+                key: CONFIG_FILE_VAR.to_string(),
+                source: path.to_string(),
+                message,
+            }),
+        }
+    }
+
+    fn add_table(&mut self, prefix: String, table: toml::Table, source: &str) {
+        for (name, value) in table {
+            let key = if prefix.is_empty() { name } else { format!("{}.{}", prefix, name) };
+            match value {
+                toml::Value::Table(nested) => self.add_table(key, nested, source),
+                value => self.insert(key, value, source.to_string()),
+            }
+        }
+    }
+
+    /// `APP_SECTION__KEY=value`; `DATABASE_URL` is also honoured since sqlx tooling relies on it. Like
+    /// any env var it overrides the file, but `APP_DATABASE__URL` takes precedence over it.
+    fn add_env(&mut self, vars: impl Iterator<Item = (String, String)>) {
+        let mut legacy_database_url = None;
+        let mut has_database_url = false;
+
+        for (name, value) in vars {
+            if name == "DATABASE_URL" {
+                legacy_database_url = Some(value);
+            } else if name != CONFIG_FILE_VAR
+                && let Some(path) = name.strip_prefix(ENV_PREFIX)
+            {
+                let key = path.split(ENV_SEPARATOR).map(str::to_lowercase).collect::<Vec<_>>().join(".");
+                has_database_url |= key == "database.url";
+                self.insert(key, toml::Value::String(value), name.clone());
+            }
+        }
+
+        if let Some(url) = legacy_database_url
+            && !has_database_url
+        {
+            self.insert("database.url".to_string(), toml::Value::String(url), "DATABASE_URL".to_string());
+        }
+    }
+
+    fn insert(&mut self, key: String, value: toml::Value, source: String) {
+        self.sources.insert(key.clone(), source.clone());
+        self.values.insert(key, (value, source));
+    }
+
+    fn optional<T: DeserializeOwned>(&mut self, key: &str) -> Option<T> {
+        let (value, source) = self.values.remove(key)?;
+
+        match convert(value) {
+            Ok(value) => Some(value),
+            Err(message) => {
+                self.errors.push(InvalidKey { key: key.to_string(), source, message });
+                None
+            }
+        }
+    }
+
+    fn get<T: DeserializeOwned>(&mut self, key: &str, default: T) -> T {
+        self.optional(key).unwrap_or(default)
+    }
+
+    fn parse<T: FromStr<Err = String>>(&mut self, key: &str, default: T) -> T {
+        self.parse_with(key, default, T::from_str)
+    }
+
+    fn parse_with<T>(&mut self, key: &str, default: T, parse: impl Fn(&str) -> Result<T, String>) -> T {
+        let Some(raw) = self.optional::<String>(key) else {
+            return default;
+        };
+
+        parse(&raw).unwrap_or_else(|message| {
+            self.invalid(key, &message);
+            default
+        })
+    }
+
+    /// A TOML array, or a comma separated string when set from the environment.
+    fn list(&mut self, key: &str) -> Vec<String> {
+        match self.values.get(key) {
+            Some((toml::Value::String(raw), _)) => {
+                let items = raw.split(',').map(str::trim).filter(|item| !item.is_empty()).map(str::to_string).collect();
+                self.values.remove(key);
+                items
+            }
+            _ => self.get(key, Vec::new()),
+        }
+    }
+
+    fn key_file(&mut self, key: &str) -> Vec<u8> {
+        let Some(path) = self.optional::<String>(key) else {
+            self.invalid(key, "is required for RS* algorithms");
+            return Vec::new();
+        };
+
+        fs::read(&path).unwrap_or_else(|e| {
+            self.invalid(key, &format!("cannot read \"{}\": {}", path, e));
+            Vec::new()
+        })
+    }
+
+    fn invalid(&mut self, key: &str, message: &str) {
+        self.errors.push(InvalidKey {
+            key: key.to_string(),
+            source: self.sources.get(key).cloned().unwrap_or_else(|| "default".to_string()),
+            message: message.to_string(),
+        });
+    }
+
+    /// Anything left over in one of the `read` sections was never read, which is almost always a typo. So is
+    /// a file section no binary knows; other binaries' sections and foreign `APP_` env vars are ignored.
+    fn finish(mut self, read: &[&str]) -> Result<(), ConfigError> {
+        for (key, (_, source)) in std::mem::take(&mut self.values) {
+            let section = key.split('.').next().unwrap_or_default();
+            if read.contains(&section) || (!SECTIONS.contains(&section) && !source.starts_with(ENV_PREFIX)) {
+                self.errors.push(InvalidKey { key, source, message: "unknown key".to_string() });
+            }
+        }
+
+        if self.errors.is_empty() {
+            Ok(())
+        } else {
+            Err(ConfigError { errors: self.errors })
+        }
+    }
+}
+
+/// Env values always arrive as strings, so `"8080"` is re-read as a TOML literal when a number is expected.
+fn convert<T: DeserializeOwned>(value: toml::Value) -> Result<T, String> {
+    let error = match value.clone().try_into::<T>() {
+        Ok(value) => return Ok(value),
+        Err(e) => e.to_string(),
+    };
+
+    if let toml::Value::String(raw) = value
+        && let Ok(mut table) = format!("v = {}", raw).parse::<toml::Table>()
+        && let Some(literal) = table.remove("v")
+        && let Ok(value) = literal.try_into::<T>()
+    {
+        return Ok(value);
+    }
+
+    Err(error.trim().to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const SECRET: &str = "0123456789abcdef0123456789abcdef";
+
+    fn load(file: &str, env: &[(&str, &str)]) -> Result<AppConfig, ConfigError> {
+        let mut reader = Reader::default();
+        reader.add_table(String::new(), file.parse().unwrap(), "config.toml");
+        reader.add_env(env.iter().map(|(name, value)| (name.to_string(), value.to_string())));
+        let config = AppConfig::read(&mut reader, 8080);
+        reader.finish(SECTIONS).map(|_| config)
+    }
+
+    fn minimal_file() -> String {
+        format!("[database]\nurl = \"postgres://file/db\"\n[jwt]\nsecret = \"{}\"\n", SECRET)
+    }
+
+    fn error_keys(err: &ConfigError) -> Vec<(&str, &str)> {
+        err.errors.iter().map(|e| (e.key.as_str(), e.source.as_str())).collect()
+    }
+
+    #[test]
+    fn env_overrides_file() {
+        let file = format!("{}[server]\nhost = \"0.0.0.0\"\nport = 9000\n", minimal_file());
+        let config = load(&file, &[("APP_SERVER__PORT", "9100")]).unwrap();
+
+        assert_eq!(config.server.host, "0.0.0.0");
+        assert_eq!(config.server.port, 9100);
+    }
+
+    #[test]
+    fn app_database_url_wins_over_database_url() {
+        let config = load(&minimal_file(), &[("DATABASE_URL", "postgres://legacy/db")]).unwrap();
+        assert_eq!(config.database.url, "postgres://legacy/db");
+
+        let config = load(&minimal_file(), &[
+            ("APP_DATABASE__URL", "postgres://app/db"),
+            ("DATABASE_URL", "postgres://legacy/db"),
+        ]).unwrap();
+        assert_eq!(config.database.url, "postgres://app/db");
+    }
+
+    #[test]
+    fn foreign_app_vars_are_ignored() {
+        let config = load(&minimal_file(), &[("APP_ENV", "production"), ("APP_NAME__FULL", "playground")]);
+
+        assert!(config.is_ok());
+    }
+
+    #[test]
+    fn env_strings_convert_to_numbers_and_bools() {
+        let config = load(&minimal_file(), &[
+            ("APP_DATABASE__MAX_CONNECTIONS", "25"),
+            ("APP_DATABASE__IDLE_TIMEOUT_SECS", "0"),
+            ("APP_DATABASE__AUTO_MIGRATE", "true"),
+        ]).unwrap();
+
+        assert_eq!(config.database.max_connections, 25);
+        assert_eq!(config.database.idle_timeout, None);
+        assert!(config.database.auto_migrate);
+    }
+
+    #[test]
+    fn non_numeric_env_value_is_reported() {
+        let err = load(&minimal_file(), &[("APP_SERVER__PORT", "eighty")]).unwrap_err();
+
+        assert_eq!(error_keys(&err), [("server.port", "APP_SERVER__PORT")]);
+    }
+
+    #[test]
+    fn unknown_keys_are_reported() {
+        let file = format!("{}[databse]\nurl = \"typo\"\n", minimal_file());
+        let err = load(&file, &[("APP_SERVER__PROT", "9000")]).unwrap_err();
+
+        assert_eq!(error_keys(&err), [("databse.url", "config.toml"), ("server.prot", "APP_SERVER__PROT")]);
+        assert!(err.errors.iter().all(|e| e.message == "unknown key"));
+    }
+
+    #[test]
+    fn collects_every_error() {
+        let err = load("", &[("APP_LOGGING__FORMAT", "xml"), ("APP_CORS__ALLOWED_ORIGINS", "example.com")]).unwrap_err();
+
+        assert_eq!(error_keys(&err), [
+            ("database.url", "default"),
+            ("jwt.secret", "default"),
+            ("cors.allowed_origins", "APP_CORS__ALLOWED_ORIGINS"),
+            ("logging.format", "APP_LOGGING__FORMAT"),
+        ]);
+    }
+
+    #[test]
+    fn cors_origins_split_on_commas() {
+        let config = load(&minimal_file(), &[
+            ("APP_CORS__ALLOWED_ORIGINS", "https://a.example.com, http://localhost:3000,"),
+        ]).unwrap();
+
+        assert_eq!(config.cors.allowed_origins, ["https://a.example.com", "http://localhost:3000"]);
+    }
+
+    #[test]
+    fn example_jwt_secret_is_refused() {
+        let err = load(&minimal_file(), &[("APP_JWT__SECRET", EXAMPLE_JWT_SECRET)]).unwrap_err();
+
+        assert_eq!(error_keys(&err), [("jwt.secret", "APP_JWT__SECRET")]);
+    }
+}
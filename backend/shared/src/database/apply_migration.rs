@@ -1,387 +1,983 @@
 use tokio::fs;
-use sqlx::{types::chrono,
-            PgPool, Postgres, Transaction,
+use sqlx::{postgres::{PgConnectOptions, PgPoolOptions}, types::chrono,
+            ConnectOptions, Connection, PgConnection, PgPool, Postgres, Transaction,
 };
 use crate::{
+    database::migration_source::{Baseline, Migration, MigrationSource},
     error::Error as AppError,
 };
+use sha2::{Digest, Sha256};
+use std::{collections::BTreeMap, path::PathBuf, str::FromStr, time::Instant};
+use tracing::Instrument;
+
+/// `pg_advisory_lock` key serialising migration runs across processes ("migrate" in ASCII).
+const MIGRATION_LOCK_KEY: i64 = 0x006d_6967_7261_7465;
+
+/// Header line that makes a script run outside a transaction, e.g. for `CREATE INDEX CONCURRENTLY`.
+const NO_TRANSACTION_DIRECTIVE: &str = "-- migrate:no-transaction";
+
+/// Checksums written before real hashing was introduced look like `custom_<version>`.
+const LEGACY_CHECKSUM_PREFIX: &[u8] = b"custom_";
+
+/// What to do when an applied migration's file was edited or deleted.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum DriftPolicy {
+    Warn,
+    Error,
+}
+
+impl FromStr for DriftPolicy {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "warn" => Ok(DriftPolicy::Warn),
+            "error" => Ok(DriftPolicy::Error),
+            _ => Err(format!("must be \"warn\" or \"error\", got \"{}\"", s)),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Direction {
+    Up,
+    Down,
+    /// A squashed snapshot standing in for every migration up to its version.
+    Baseline,
+}
+
+impl Direction {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Direction::Up => "up",
+            Direction::Down => "down",
+            Direction::Baseline => "baseline",
+        }
+    }
+}
+
+/// An applied migration that no longer matches its file.
+#[derive(Debug, Clone)]
+pub enum Drift {
+    Changed { version: i64, description: String, file: String },
+    Missing { version: i64, description: String },
+}
+
+impl std::fmt::Display for Drift {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Drift::Changed { version, description, file } => write!(f, "{} ({}) changed after it was applied: {}", version, description, file),
+            Drift::Missing { version, description } => write!(f, "{} ({}) was applied but its file is missing", version, description),
+        }
+    }
+}
+
+/// A migration known from its file, the tracking table, or both.
+#[derive(Debug, Clone)]
+pub struct MigrationStatus {
+    pub version: i64,
+    pub description: String,
+    /// `None` while the migration is pending.
+    pub installed_on: Option<chrono::DateTime<chrono::Utc>>,
+    /// The last attempt failed; the migration counts as pending and is retried by the next run.
+    pub failed: bool,
+    /// `false` when the tracking table lists a version whose file no longer exists.
+    pub has_file: bool,
+}
+
+/// The exact scripts a run will execute, in order. Built by the `plan_*` methods so it can be
+/// printed (`--dry-run`) or passed to `execute_plan`.
+#[derive(Debug, Clone, Default)]
+pub struct MigrationPlan {
+    pub steps: Vec<PlannedStep>,
+}
+
+#[derive(Debug, Clone)]
+pub struct PlannedStep {
+    pub direction: Direction,
+    pub migration: Migration,
+    /// For `Direction::Baseline`, the migrations the snapshot replaces; empty otherwise.
+    pub covers: Vec<Migration>,
+}
+
+impl MigrationPlan {
+    pub fn is_empty(&self) -> bool {
+        self.steps.is_empty()
+    }
+}
+
+impl PlannedStep {
+    pub fn file(&self) -> String {
+        match self.direction {
+            Direction::Up | Direction::Baseline => self.migration.up_file.clone(),
+            Direction::Down => self.migration.down_file(),
+        }
+    }
+
+    pub fn sql(&self) -> &str {
+        match self.direction {
+            Direction::Up | Direction::Baseline => &self.migration.up_sql,
+            Direction::Down => self.migration.down_sql.as_deref().unwrap_or_default(),
+        }
+    }
+}
+
+/// The stored checksums of a successfully applied migration.
+#[derive(sqlx::FromRow)]
+struct AppliedChecksums {
+    version: i64,
+    description: String,
+    checksum: Vec<u8>,
+    down_checksum: Option<Vec<u8>>,
+}
+
+/// Holds the session lock on its own connection, outside the pool, so migrating still works with
+/// `max_connections = 1`. Dropping it closes the connection, which releases the lock even on error paths.
+pub struct MigrationLock {
+    conn: PgConnection,
+}
+
+impl MigrationLock {
+    pub async fn release(mut self) -> Result<(), AppError> {
+        sqlx::query("SELECT pg_advisory_unlock($1)")
+            .bind(MIGRATION_LOCK_KEY)
+            .execute(&mut self.conn)
+            .await?;
+        self.conn.close().await?;
+
+        tracing::debug!("마이그레이션 잠금 해제");
+        Ok(())
+    }
+}
 
 #[derive(Clone)]
 pub struct MigrationManager {
-    migrations_dir: String, // \migrations
+    source: MigrationSource,
 }
 
 impl MigrationManager {
-    pub async fn new(migrations_dir: String) -> Result<Self, sqlx::Error> {
-        Ok(Self { migrations_dir })
+    pub fn new(source: MigrationSource) -> Self {
+        Self { source }
     }
 
+    /// Creates `_schema_migrations` and, the first time, imports the rows of the legacy `_sqlx_migrations`
+    /// table, whose name clashed with sqlx's own and whose `version` column was `VARCHAR`.
     pub async fn ensure_migration_table(pool: &PgPool) -> Result<(), AppError> {
-        println!("마이그레이션 추적 테이블 확인 중...");
+        let mut tx = pool.begin().await?;
 
         sqlx::query(
-            "CREATE TABLE IF NOT EXISTS _sqlx_migrations (
-                version VARCHAR NOT NULL PRIMARY KEY,
+            "CREATE TABLE IF NOT EXISTS _schema_migrations (
+                version BIGINT NOT NULL PRIMARY KEY,
                 description TEXT NOT NULL,
                 installed_on TIMESTAMPTZ NOT NULL DEFAULT NOW(),
                 success BOOLEAN NOT NULL,
                 checksum BYTEA NOT NULL,
-                execution_time BIGINT NOT NULL
+                execution_time BIGINT NOT NULL,
+                down_checksum BYTEA
             );"
         )
-        .execute(pool)
+        .execute(&mut *tx)
         .await?;
 
-        println!("_sqlx_migrations 테이블 준비 완료");
+        // Tables created before down scripts were checksummed; their rows stay NULL until `repair`.
+        sqlx::query("ALTER TABLE _schema_migrations ADD COLUMN IF NOT EXISTS down_checksum BYTEA")
+            .execute(&mut *tx)
+            .await?;
+
+        let has_legacy_table: bool = sqlx::query_scalar(
+            "SELECT to_regclass('_sqlx_migrations') IS NOT NULL"
+        )
+        .fetch_one(&mut *tx)
+        .await?;
+
+        if has_legacy_table {
+            let imported = sqlx::query(
+                "INSERT INTO _schema_migrations (version, description, installed_on, success, checksum, execution_time)
+                 SELECT version::TEXT::BIGINT, description, installed_on, success, checksum, execution_time
+                 FROM _sqlx_migrations
+                 WHERE version::TEXT ~ '^[0-9]+$'
+                   AND NOT EXISTS (SELECT 1 FROM _schema_migrations)"
+            )
+            .execute(&mut *tx)
+            .await?
+            .rows_affected();
+
+            if imported > 0 {
+                tracing::info!(imported, "_sqlx_migrations 기록을 _schema_migrations로 가져옴");
+            }
+        }
+
+        tx.commit().await?;
 
         Ok(())
     }
 
+    /// Blocks until no other process is migrating this database. Hold the returned lock for the whole
+    /// run so a second process waits, then finds nothing pending.
+    pub async fn lock(pool: &PgPool) -> Result<MigrationLock, AppError> {
+        let mut conn = pool.connect_options().connect().await?;
+
+        tracing::debug!("마이그레이션 잠금 대기 중");
+        sqlx::query("SELECT pg_advisory_lock($1)")
+            .bind(MIGRATION_LOCK_KEY)
+            .execute(&mut conn)
+            .await?;
+        tracing::debug!("마이그레이션 잠금 획득");
+
+        Ok(MigrationLock { conn })
+    }
+
     pub async fn get_applied_migrations(pool: &PgPool) -> Result<Vec<i64>, AppError> {
         let versions: Vec<i64> = sqlx::query_scalar(
-            "SELECT version FROM _sqlx_migrations ORDER BY version"
+            "SELECT version FROM _schema_migrations WHERE success ORDER BY version"
         )
         .fetch_all(pool)
         .await?;
 
-        println!("실행된 마이그레이션: {:?}", versions);
+        tracing::debug!(?versions, "실행된 마이그레이션");
         Ok(versions)
     }
 
+    #[tracing::instrument(name = "migration", skip(pool, sql), fields(direction = "up"))]
     pub async fn run_migration(pool: &PgPool , version: i64, description:&str, sql: &str) -> Result<(), AppError> {
-        let exists: bool = sqlx::query_scalar(
-            "SELECT EXISTS(SELECT 1 FROM _sqlx_migrations WHERE version = $1)"
-        )
-        .bind(version)
-        .fetch_one(pool)
-        .await?;
-
-        if exists {
-            println!("마이그레이션 {} 이미 실행됨", version);
+        if Self::get_applied_migrations(pool).await?.contains(&version) {
+            tracing::debug!("이미 실행됨");
             return Ok(());
         }
 
-        println!("마이그레이션 {} 실행 중...", version);
+        let migration = Migration {
+            version,
+            description: description.to_string(),
+            up_file: format!("{}_{}.up.sql", version, description.replace(' ', "_")),
+            up_sql: sql.to_string(),
+            down_sql: None,
+        };
+        Self::execute(pool, &PlannedStep { direction: Direction::Up, migration, covers: Vec::new() }).await?;
 
-        let mut tx: Transaction<Postgres> = pool.begin().await?;
+        tracing::info!("마이그레이션 완료");
+        Ok(())
+    }
 
-        sqlx::query(sql)
-            .execute(&mut *tx)
-            .await?;
-        
-        sqlx::query(
-            "INSERT INTO _sqlx_migrations (version, description, installed_on, success, checksum, execution_time)
-             VALUES ($1, $2, $3, $4, $5, $6)"
-        )
-        .bind(version)
-        .bind(description)
-        .bind(chrono::Utc::now())
-        .bind(true)
-        .bind(format!("custom_{}", version).as_bytes())
-        .bind(0i64)
-        .execute(&mut *tx)
-        .await?;
+    /// Whether `sql` should run inside a transaction: yes unless its leading comment block
+    /// contains `-- migrate:no-transaction`.
+    pub fn runs_in_transaction(sql: &str) -> bool {
+        !sql.lines()
+            .map(str::trim)
+            .take_while(|line| line.is_empty() || line.starts_with("--"))
+            .any(|line| line == NO_TRANSACTION_DIRECTIVE)
+    }
 
-        tx.commit().await?;
+    /// Runs one step and updates the tracking rows. Transactional scripts commit together with the
+    /// rows; the others run statement by statement, so a failure can leave them half-applied.
+    /// Failures are recorded with `success = false`, except a transactional rollback or baseline
+    /// failure, which leaves the tracking table as it was.
+    async fn execute(pool: &PgPool, step: &PlannedStep) -> Result<(), AppError> {
+        let migration = &step.migration;
+        let sql = match step.direction {
+            Direction::Up | Direction::Baseline => migration.up_sql.as_str(),
+            Direction::Down => migration.down_sql.as_deref().ok_or_else(|| {
+                tracing::error!("down.sql 파일을 찾을 수 없습니다");
+                AppError::NotFound(format!("down 파일 없음: {}", migration.down_file()))
+            })?,
+        };
+        let transactional = Self::runs_in_transaction(sql);
+        let started = Instant::now();
+
+        let result = if transactional {
+            async {
+                let mut tx: Transaction<Postgres> = pool.begin().await?;
+                sqlx::raw_sql(sql).execute(&mut *tx).await?;
+                Self::record_step(&mut tx, step, true, started.elapsed().as_nanos() as i64).await?;
+                tx.commit().await
+            }
+            .await
+        } else {
+            async {
+                for statement in split_statements(sql) {
+                    sqlx::raw_sql(statement).execute(pool).await?;
+                }
+                Self::record_step(&mut *pool.acquire().await?, step, true, started.elapsed().as_nanos() as i64).await
+            }
+            .await
+        };
 
-        println!("마이그레이션 {} 완료", version);
-        Ok(())
-    }
+        if let Err(e) = result {
+            tracing::error!(error = %e, transactional, "마이그레이션 실패");
 
-pub async fn extract_up_migration_files(&self) -> Result<Vec<String>, AppError> {
-    println!("마이그레이션 디렉토리: {}", self.migrations_dir);
-    println!("디렉토리 존재 여부: {}", std::path::Path::new(&self.migrations_dir).exists());
-    
-    let mut up_files = Vec::new();
-    
-    match tokio::fs::read_dir(&self.migrations_dir).await {
-        Ok(mut entries) => {
-            println!("디렉토리 읽기 성공, 파일 스캔 시작...");
-            
-            while let Some(entry) = entries.next_entry().await? {
-                let file_type = entry.file_type().await?;
-                let file_name = entry.file_name();
-                let file_path = entry.path();
-                
-                println!("발견된 항목: {:?} (파일: {})", file_path, file_type.is_file());
-                
-                if file_type.is_file() {
-                    if let Some(name_str) = file_name.to_str() {
-                        println!("파일명: {}", name_str);
-                        
-                        if name_str.ends_with(".up.sql") {
-                            println!(".up.sql 파일 발견: {}", name_str);
-                            up_files.push(name_str.to_string());
-                        } else {
-                            println!(".up.sql 패턴 불일치: {}", name_str);
-                        }
-                    } else {
-                        println!("파일명을 문자열로 변환 실패: {:?}", file_name);
-                    }
+            if !(transactional && step.direction != Direction::Up) {
+                if !transactional {
+                    tracing::warn!("Non-transactional migration failed part-way; check the schema before retrying");
                 }
+                Self::record_step(&mut *pool.acquire().await?, step, false, started.elapsed().as_nanos() as i64).await?;
             }
-        }
-        Err(e) => {
-            println!("디렉토리 읽기 실패: {}", e);
+
             return Err(e.into());
         }
+
+        Ok(())
     }
-    
-    up_files.sort();
-    println!("최종 발견된 .up.sql 파일들: {:?}", up_files);
-    println!("총 {} 개의 .up.sql 파일 발견", up_files.len());
-    
-    Ok(up_files)
-}
 
-    pub async fn extract_down_migration_files(&self) -> Result<Vec<String>, AppError> {
-        let mut down_files = Vec::new();
-
-        let mut entries = tokio::fs::read_dir(&self.migrations_dir).await?;
-        while let Some(entry) = entries.next_entry().await? {
-            let file_type = entry.file_type().await?;
-            if file_type.is_file() {
-                let down_file_name = entry.file_name();
-                if let Some(name_str) = down_file_name.to_str() {
-                    if name_str.ends_with(".down.sql") {
-                       down_files.push(name_str.to_string());
-                    }
+    /// A baseline stamps every migration it covers, each with its own checksum, so drift detection
+    /// and rollbacks keep working per file.
+    async fn record_step(conn: &mut PgConnection, step: &PlannedStep, success: bool, execution_time: i64) -> Result<(), sqlx::Error> {
+        match step.direction {
+            Direction::Baseline => {
+                for migration in &step.covers {
+                    Self::record(&mut *conn, migration, Direction::Up, success, execution_time).await?;
                 }
+                Ok(())
             }
+            direction => Self::record(conn, &step.migration, direction, success, execution_time).await,
         }
-
-        down_files.sort();
-        down_files.reverse();
-        Ok(down_files)
     }
 
-    fn extract_version_and_description_from_up_file(filename: &str) -> Option<(i64, String)> {
-        let without_extension = filename.strip_suffix(".up.sql")?;
+    /// A successful up upserts the row, a successful down deletes it, and a failure in either
+    /// direction upserts it with `success = false`.
+    async fn record<'e>(
+        executor: impl sqlx::PgExecutor<'e>,
+        migration: &Migration,
+        direction: Direction,
+        success: bool,
+        execution_time: i64,
+    ) -> Result<(), sqlx::Error> {
+        if success && direction == Direction::Down {
+            sqlx::query("DELETE FROM _schema_migrations WHERE version = $1")
+                .bind(migration.version)
+                .execute(executor)
+                .await?;
+            return Ok(());
+        }
 
-        let mut parts = without_extension.splitn(2, '_');
+        sqlx::query(
+            "INSERT INTO _schema_migrations (version, description, installed_on, success, checksum, execution_time, down_checksum)
+             VALUES ($1, $2, $3, $4, $5, $6, $7)
+             ON CONFLICT (version) DO UPDATE SET
+                description = EXCLUDED.description,
+                installed_on = EXCLUDED.installed_on,
+                success = EXCLUDED.success,
+                checksum = EXCLUDED.checksum,
+                execution_time = EXCLUDED.execution_time,
+                down_checksum = EXCLUDED.down_checksum"
+        )
+        .bind(migration.version)
+        .bind(&migration.description)
+        .bind(chrono::Utc::now())
+        .bind(success)
+        .bind(Self::checksum(&migration.up_sql))
+        .bind(execution_time)
+        .bind(Self::down_checksum(migration))
+        .execute(executor)
+        .await?;
 
-        let version_str = parts.next()?;
-        let version = version_str.parse::<i64>().ok()?;
+        Ok(())
+    }
 
-        let description = if let Some(desc_part) = parts.next() {
-            desc_part
-                .replace('_', " ")
-                .trim()
-                .to_lowercase()
-        } else {
-            "migration".to_string()
-        };
+    /// SHA-256 of the migration SQL, stored in the tracking table to detect later edits.
+    fn checksum(sql: &str) -> Vec<u8> {
+        Sha256::digest(sql.as_bytes()).to_vec()
+    }
 
-        Some((version, description))
+    async fn applied_checksums(pool: &PgPool) -> Result<Vec<AppliedChecksums>, AppError> {
+        Ok(sqlx::query_as(
+            "SELECT version, description, checksum, down_checksum FROM _schema_migrations WHERE success ORDER BY version"
+        )
+        .fetch_all(pool)
+        .await?)
     }
 
-    fn extract_version_from_down_file(filename: &str) -> Option<i64> {
-        let version_part = filename
-            .trim_end_matches(".down.sql")
-            .split('_')
-            .next()?;
+    /// Checksum of the down script, `None` when the migration has none.
+    fn down_checksum(migration: &Migration) -> Option<Vec<u8>> {
+        migration.down_sql.as_deref().map(Self::checksum)
+    }
 
-        version_part.parse().ok()
+    /// Migrations keyed by version, for lookups that must not depend on the description.
+    async fn migrations_by_version(&self) -> Result<BTreeMap<i64, Migration>, AppError> {
+        Ok(self.source.load().await?
+            .into_iter()
+            .map(|migration| (migration.version, migration))
+            .collect())
     }
 
-    fn find_down_file_for_up(&self,up_filename: &str) -> Option<String> {
-        let down_filename = up_filename.replace(".up.sql", ".down.sql");
+    async fn pending_migrations(&self, pool: &PgPool) -> Result<Vec<Migration>, AppError> {
+        let applied_versions: Vec<i64> = Self::get_applied_migrations(pool).await?;
 
-        let down_path = format!("{}/{}", self.migrations_dir, down_filename);
-        if std::path::Path::new(&down_path).exists() {
-            Some(down_filename)
-        } else {
-            None
-        }
+        let pending: Vec<Migration> = self.source.load().await?
+            .into_iter()
+            .inspect(|migration| {
+                tracing::trace!(file = %migration.up_file, version = migration.version, has_down = migration.down_sql.is_some(), "마이그레이션 파일");
+            })
+            .filter(|migration| !applied_versions.contains(&migration.version))
+            .collect();
+
+        Ok(pending)
     }
 
     pub async fn find_pending_up_migrations(&self, pool: &PgPool) -> Result<Vec<(i64, String)>, AppError> {
-        let up_files = Self::extract_up_migration_files(&self).await?;
+        let pending: Vec<(i64, String)> = self.pending_migrations(pool).await?
+            .into_iter()
+            .map(|migration| (migration.version, migration.description))
+            .collect();
 
-        let applied_versions: Vec<i64> = Self::get_applied_migrations(pool).await?;
-
-        println!("발견된 .up.sql 파일들:");
-        for up_file in &up_files {
-            if let Some((version, description)) = Self::extract_version_and_description_from_up_file(up_file) {
-                let down_file = self.find_down_file_for_up(up_file);
-                println!("{} -> 버전: {}, 설명: {}, down: {:?}", up_file, version, description, down_file);
-                }
-            }
-        
-        println!("\n DB에 적용된 마이그레이션:");
-        for version in &applied_versions {
-            println!("{}", version);
-        }
-     
-        let mut pending = Vec::new();
-
-        println!("파일 시스템의 마이그레이션:");
-        for up_file in up_files {
-            if let Some((version, description)) = Self::extract_version_and_description_from_up_file(&up_file) {
-                if !applied_versions.contains(&version) {
-                    pending.push((version, description));
-                }
-            }
-        }
-
-        println!("\n 실행 대기 중인 .up.sql 마이그레이션:");
-        for (version, description) in &pending {
-            println!("{} ({})", version, description);
-        }
+        tracing::debug!(?pending, "실행 대기 중인 마이그레이션");
 
         Ok(pending)
     }
 
     pub async fn run_pending_up_migrations(&self, pool: &PgPool) -> Result<(), AppError> {
-        let pending = Self::find_pending_up_migrations(&self, pool).await?;
+        self.run_up_to(pool, None).await
+    }
 
-        if pending.is_empty() {
-            println!("실행할 .up.sql 마이그레이션이 없습니다. 이미 최신 상태!");
-            return Ok(());
+    /// Applies pending migrations in order, stopping after `target` when one is given.
+    pub async fn run_up_to(&self, pool: &PgPool, target: Option<i64>) -> Result<(), AppError> {
+        let plan = self.plan_up(pool, target).await?;
+        self.execute_plan(pool, &plan).await
+    }
+
+    /// Rolls back every applied migration above `target_version`, newest first. `target_version` itself stays applied.
+    pub async fn rollback_to(&self, target_version: i64, pool: &PgPool) -> Result<(), AppError> {
+        let plan = self.plan_down(pool, target_version).await?;
+        self.execute_plan(pool, &plan).await
+    }
+
+    /// Rolls back exactly `target_version`, if it is applied.
+    pub async fn rollback_single(&self, target_version: i64, pool: &PgPool) -> Result<(), AppError> {
+        let plan = self.plan_single_down(pool, target_version).await?;
+        self.execute_plan(pool, &plan).await
+    }
+
+    /// Pending migrations up to and including `target`, oldest first. On a fresh database with a
+    /// baseline at or below `target`, the baseline replaces the migrations it covers.
+    pub async fn plan_up(&self, pool: &PgPool, target: Option<i64>) -> Result<MigrationPlan, AppError> {
+        let mut plan = self.plan_up_files(pool, target).await?;
+
+        let fresh: bool = sqlx::query_scalar("SELECT NOT EXISTS (SELECT 1 FROM _schema_migrations)")
+            .fetch_one(pool)
+            .await?;
+
+        if fresh
+            && let Some(Baseline { version, file, sql }) = self.source.load_baseline().await?
+            && target.is_none_or(|target| target >= version) {
+            let (covers, rest): (Vec<PlannedStep>, Vec<PlannedStep>) = plan.steps
+                .into_iter()
+                .partition(|step| step.migration.version <= version);
+
+            let migration = Migration { version, description: "baseline".to_string(), up_file: file, up_sql: sql, down_sql: None };
+            let covers = covers.into_iter().map(|step| step.migration).collect();
+            plan.steps = std::iter::once(PlannedStep { direction: Direction::Baseline, migration, covers })
+                .chain(rest)
+                .collect();
         }
 
-        for (version, description) in pending {
-            println!("UP 마이그레이션 실행 중: {} ({})", version, description);
+        Ok(plan)
+    }
 
-            let up_file_path = format!("{}/{}", self.migrations_dir, description);
-            let sql_content = fs::read_to_string(up_file_path).await?;
+    /// Like `plan_up`, but always replays the individual files.
+    async fn plan_up_files(&self, pool: &PgPool, target: Option<i64>) -> Result<MigrationPlan, AppError> {
+        let steps = self.pending_migrations(pool).await?
+            .into_iter()
+            .filter(|migration| target.is_none_or(|target| migration.version <= target))
+            .map(|migration| PlannedStep { direction: Direction::Up, migration, covers: Vec::new() })
+            .collect();
 
-            let mut tx = pool.begin().await?;
+        Ok(MigrationPlan { steps })
+    }
 
-            sqlx::raw_sql(&sql_content)
-                .execute(&mut *tx)
-                .await?;
+    /// Applied migrations above `target_version`, newest first. Fails before anything runs if one of
+    /// them has no file or no down script.
+    pub async fn plan_down(&self, pool: &PgPool, target_version: i64) -> Result<MigrationPlan, AppError> {
+        let versions: Vec<i64> = Self::get_applied_migrations(pool).await?
+            .into_iter()
+            .rev()
+            .filter(|version| *version > target_version)
+            .collect();
 
-            sqlx::query("INSERT INTO _sqlx_migrations (version, description, installed_on, success, checksum, execution_time)
-                        VALUES ($1, $2, $3, $4, $5, $6)"
-                )
-                .bind(version)
-                .bind(description)
-                .bind(chrono::Utc::now())
-                .bind(true)
-                .bind(format!("custom_{}", version).as_bytes())
-                .bind(0i64)
-                .execute(&mut *tx)
-                .await?;
+        self.plan_rollback(versions).await
+    }
+
+    async fn plan_single_down(&self, pool: &PgPool, version: i64) -> Result<MigrationPlan, AppError> {
+        let versions = Self::get_applied_migrations(pool).await?
+            .into_iter()
+            .filter(|applied| *applied == version)
+            .collect();
+
+        self.plan_rollback(versions).await
+    }
+
+    /// Rolls back the latest applied migration, then applies it again.
+    pub async fn plan_redo(&self, pool: &PgPool) -> Result<MigrationPlan, AppError> {
+        let Some(latest) = Self::get_applied_migrations(pool).await?.into_iter().max() else {
+            return Ok(MigrationPlan::default());
+        };
+
+        let mut plan = self.plan_single_down(pool, latest).await?;
+        let reapply = plan.steps.iter()
+            .map(|step| PlannedStep { direction: Direction::Up, migration: step.migration.clone(), covers: Vec::new() })
+            .collect::<Vec<_>>();
+        plan.steps.extend(reapply);
+
+        Ok(plan)
+    }
 
-            tx.commit().await?;
+    async fn plan_rollback(&self, versions: Vec<i64>) -> Result<MigrationPlan, AppError> {
+        let mut migrations = self.migrations_by_version().await?;
 
-            println!("UP 마이그레이션 {} 완료!", version);
+        let steps = versions.into_iter()
+            .map(|version| {
+                let migration = migrations.remove(&version).ok_or_else(|| {
+                    AppError::NotFound(format!("마이그레이션 파일 없음: {}", version))
+                })?;
+                if migration.down_sql.is_none() {
+                    return Err(AppError::NotFound(format!("down 파일 없음: {}", migration.down_file())));
+                }
+
+                Ok(PlannedStep { direction: Direction::Down, migration, covers: Vec::new() })
+            })
+            .collect::<Result<Vec<_>, AppError>>()?;
+
+        Ok(MigrationPlan { steps })
+    }
+
+    /// Runs the steps in order, stopping at the first failure.
+    pub async fn execute_plan(&self, pool: &PgPool, plan: &MigrationPlan) -> Result<(), AppError> {
+        if plan.is_empty() {
+            tracing::info!("실행할 마이그레이션이 없습니다. 이미 최신 상태");
+            return Ok(());
+        }
+
+        for step in &plan.steps {
+            let migration = &step.migration;
+            let span = tracing::info_span!(
+                "migration",
+                version = migration.version,
+                description = %migration.description,
+                direction = step.direction.as_str(),
+            );
+            async {
+                tracing::debug!(file = %step.file(), "마이그레이션 실행");
+                Self::execute(pool, step).await?;
+                tracing::info!("마이그레이션 완료");
+
+                Ok::<(), AppError>(())
+            }
+            .instrument(span)
+            .await?;
         }
 
-        println!("모든 UP 마이그레이션 실행 완료!");
+        tracing::info!(steps = plan.steps.len(), "모든 마이그레이션 실행 완료");
         Ok(())
     }
 
-    pub async fn rollback_to(&self, target_version: i64, pool: &PgPool) -> Result<(), AppError> {
-        println!("목표: {}로 롤백", target_version);
+    /// Every migration with its applied timestamp, ordered by version.
+    pub async fn status(&self, pool: &PgPool) -> Result<Vec<MigrationStatus>, AppError> {
+        let mut migrations = BTreeMap::new();
+
+        for Migration { version, description, .. } in self.source.load().await? {
+            migrations.insert(version, MigrationStatus { version, description, installed_on: None, failed: false, has_file: true });
+        }
 
-        let versions_to_rollback: Vec<i64> = sqlx::query_scalar(
-            "SELECT version, description FROM _sqlx_migrations
-            WHERE version > $1 ORDER BY version DESC"
+        let recorded: Vec<(i64, String, chrono::DateTime<chrono::Utc>, bool)> = sqlx::query_as(
+            "SELECT version, description, installed_on, success FROM _schema_migrations ORDER BY version"
         )
-        .bind(target_version)
         .fetch_all(pool)
         .await?;
 
-        if versions_to_rollback.is_empty() {
-            println!("롤백할 마이그레이션이 없습니다.");
-            return Ok(());
+        for (version, description, installed_on, success) in recorded {
+            let status = migrations.entry(version)
+                .or_insert(MigrationStatus { version, description, installed_on: None, failed: false, has_file: false });
+            if success {
+                status.installed_on = Some(installed_on);
+            } else {
+                status.failed = true;
+            }
         }
 
-        println!("롤백할 버전들: {:?}", versions_to_rollback);
+        Ok(migrations.into_values().collect())
+    }
 
-        let down_files = self.extract_down_migration_files().await?;
+    /// Rolls back the most recently applied migration and applies it again.
+    pub async fn redo(&self, pool: &PgPool) -> Result<(), AppError> {
+        let plan = self.plan_redo(pool).await?;
+        self.execute_plan(pool, &plan).await
+    }
 
-        for version in versions_to_rollback {
-            println!("버전 {} 롤백 중...", version);
+    /// Creates an empty `.up.sql`/`.down.sql` pair numbered after the highest existing version. For the
+    /// embedded source the pair is written to the source tree and picked up on the next build.
+    pub async fn create_migration(&self, name: &str) -> Result<(PathBuf, PathBuf), AppError> {
+        let slug: String = name.trim()
+            .chars()
+            .map(|c| if c.is_ascii_alphanumeric() { c.to_ascii_lowercase() } else { '_' })
+            .collect();
+        let slug = slug.trim_matches('_');
+        if slug.is_empty() {
+            return Err(AppError::InvalidInput(format!("Invalid migration name: {:?}", name)));
+        }
 
-            let down_filename = down_files.iter()
-                .find(|filename|{
-                    Self::extract_version_from_down_file(filename) == Some(version)
-                });
+        let next_version = self.source.load().await?
+            .iter()
+            .map(|migration| migration.version)
+            .max()
+            .unwrap_or(0) + 1;
 
-            if let Some(down_filename) = down_filename {
-                let down_file_path = format!("{}/{}", self.migrations_dir, down_filename);
-                let sql_content = fs::read_to_string(down_file_path).await?;
+        let dir = self.source.dir();
+        let up_path = dir.join(format!("{:03}_{}.up.sql", next_version, slug));
+        let down_path = dir.join(format!("{:03}_{}.down.sql", next_version, slug));
 
-                let mut tx = pool.begin().await?;
+        fs::write(&up_path, format!("-- {}\n", name.trim())).await?;
+        fs::write(&down_path, format!("-- Revert {}\n", name.trim())).await?;
 
-                sqlx::query(&sql_content)
-                    .execute(&mut *tx)
-                    .await?;
+        tracing::info!(version = next_version, up = ?up_path, down = ?down_path, "마이그레이션 파일 생성");
+        Ok((up_path, down_path))
+    }
 
-                sqlx::query("DELETE FROM _sqlx_migrations WHERE version > $1")
-                    .bind(version)
-                    .execute(&mut *tx)
-                    .await?;
+    /// Writes `{version}_baseline.baseline.sql`, a `pg_dump --schema-only` of a scratch database with
+    /// every migration up to `version` (default: the latest) replayed from its file. The scratch
+    /// database is created next to `database_url`'s and always dropped. Data inserted by migrations
+    /// is not part of the snapshot.
+    pub async fn create_baseline(&self, database_url: &str, version: Option<i64>) -> Result<PathBuf, AppError> {
+        let migrations = self.source.load().await?;
+        let version = match version {
+            Some(version) if migrations.iter().any(|migration| migration.version == version) => version,
+            Some(version) => return Err(AppError::InvalidInput(format!("No migration with version {}", version))),
+            None => migrations.last()
+                .map(|migration| migration.version)
+                .ok_or_else(|| AppError::InvalidInput("No migrations to baseline".to_string()))?,
+        };
 
-                tx.commit().await?;
+        let admin = PgConnectOptions::from_str(database_url)?;
+        let scratch = format!("baseline_{}", uuid::Uuid::new_v4().simple());
+        let scratch_options = admin.clone().database(&scratch);
+
+        let mut conn = admin.connect().await?;
+        sqlx::raw_sql(&format!("CREATE DATABASE {}", scratch)).execute(&mut conn).await?;
+        tracing::info!(%scratch, version, "임시 데이터베이스에 마이그레이션 재실행");
+
+        let dump = async {
+            let pool = PgPoolOptions::new().max_connections(2).connect_with(scratch_options.clone()).await?;
+            Self::ensure_migration_table(&pool).await?;
+            let plan = self.plan_up_files(&pool, Some(version)).await?;
+            self.execute_plan(&pool, &plan).await?;
+            pool.close().await;
+
+            // The password goes through the environment, not the command line other users can read.
+            let (dbname, password) = without_password(&with_database(database_url, &scratch));
+            let mut pg_dump = tokio::process::Command::new("pg_dump");
+            pg_dump
+                .args(["--schema-only", "--no-owner", "--no-privileges", "--exclude-table=_schema_migrations"])
+                .arg(format!("--dbname={}", dbname));
+            if let Some(password) = password {
+                pg_dump.env("PGPASSWORD", password);
+            }
 
-                println!("Rolled back to 버전 {} ({})", version, down_filename);
-            } else {
-                println!("버전 {}의 down.sql 파일을 찾을 수 없습니다!", version);
-                return Err(AppError::NotFound("down 파일 없음:".to_string()));
+            let output = pg_dump
+                .output()
+                .await
+                .map_err(|e| AppError::Server(format!("Failed to run pg_dump: {}", e)))?;
+            if !output.status.success() {
+                return Err(AppError::Server(format!("pg_dump failed: {}", String::from_utf8_lossy(&output.stderr).trim())));
             }
+
+            Ok::<String, AppError>(String::from_utf8_lossy(&output.stdout).into_owned())
         }
+        .await;
 
-        println!("롤백 완료!");
-        Ok(())
+        sqlx::raw_sql(&format!("DROP DATABASE IF EXISTS {} WITH (FORCE)", scratch)).execute(&mut conn).await?;
+        let dump = dump?;
+
+        let path = self.source.dir().join(format!("{:03}_baseline.baseline.sql", version));
+        fs::write(&path, Self::baseline_sql(&dump, version)).await?;
+
+        tracing::info!(version, path = ?path, "베이스라인 생성");
+        Ok(path)
     }
 
-    pub async fn rollback_single(&self, target_version: i64, pool: &PgPool) -> Result<(), AppError> {
-        println!("목표: {}로 롤백", target_version);
+    /// Makes a `pg_dump` script safe to run on a pooled connection: session settings become
+    /// transaction-local, the empty `search_path` (dump names are schema-qualified) is dropped so the
+    /// tracking row can still be written, and psql meta-commands and `client_encoding` are removed.
+    fn baseline_sql(dump: &str, version: i64) -> String {
+        let mut sql = format!(
+            "-- Baseline schema at version {}, generated by `migrate baseline`. Do not edit; regenerate instead.\n",
+            version,
+        );
+
+        for line in dump.lines() {
+            if line.starts_with('\\') || line.contains("set_config('search_path'") || line.starts_with("SET client_encoding") {
+                continue;
+            }
+            match line.strip_prefix("SET ") {
+                Some(setting) => sql.push_str(&format!("SET LOCAL {}", setting)),
+                None => sql.push_str(line),
+            }
+            sql.push('\n');
+        }
 
-        let versions_to_rollback: Vec<i64> = sqlx::query_scalar(
-            "SELECT version, description FROM _sqlx_migrations
-            WHERE version = $1 ORDER BY version DESC"
-        )
-        .bind(target_version)
-        .fetch_all(pool)
-        .await?;
+        sql
+    }
+
+    /// Compares every applied migration's stored checksums with its up and down files in the source. Rows still
+    /// carrying a legacy placeholder checksum can't be verified and are skipped until `repair` stamps them; a
+    /// NULL down checksum (no down script when applied, or a row older than the column) is not checked, so a
+    /// down script may be added later, but an edited or deleted one is reported.
+    pub async fn find_drift(&self, pool: &PgPool) -> Result<Vec<Drift>, AppError> {
+        let migrations = self.migrations_by_version().await?;
+        let mut drift = Vec::new();
+        for AppliedChecksums { version, description, checksum, down_checksum } in Self::applied_checksums(pool).await? {
+            let Some(migration) = migrations.get(&version) else {
+                drift.push(Drift::Missing { version, description });
+                continue;
+            };
+
+            if checksum.starts_with(LEGACY_CHECKSUM_PREFIX) {
+                tracing::debug!(version, "레거시 체크섬, 검증 생략");
+                continue;
+            }
+
+            if Self::checksum(&migration.up_sql) != checksum {
+                drift.push(Drift::Changed { version, description, file: migration.up_file.clone() });
+            } else if down_checksum.is_some() && Self::down_checksum(migration) != down_checksum {
+                drift.push(Drift::Changed { version, description, file: migration.down_file() });
+            }
+        }
+
+        Ok(drift)
+    }
 
-        if versions_to_rollback.is_empty() {
-            println!("롤백할 마이그레이션이 없습니다.");
+    /// Logs any drift, and fails under `DriftPolicy::Error`.
+    pub async fn check_drift(&self, pool: &PgPool, policy: DriftPolicy) -> Result<(), AppError> {
+        let drift = self.find_drift(pool).await?;
+        if drift.is_empty() {
             return Ok(());
         }
 
-        println!("롤백할 버전들: {:?}", versions_to_rollback);
+        for item in &drift {
+            tracing::warn!(%item, "Applied migration drifted from its file");
+        }
 
-        let down_files = self.extract_down_migration_files().await?;
+        match policy {
+            DriftPolicy::Warn => Ok(()),
+            DriftPolicy::Error => Err(AppError::Server(format!(
+                "{} applied migration(s) differ from their files; restore them or run `migrate repair`",
+                drift.len(),
+            ))),
+        }
+    }
 
-        for version in versions_to_rollback {
-            println!("버전 {} 롤백 중...", version);
+    /// Re-stamps the up and down checksums of every applied migration whose files changed, accepting the edit.
+    /// Rows whose file is missing are left alone. Returns the versions updated.
+    pub async fn repair(&self, pool: &PgPool) -> Result<Vec<i64>, AppError> {
+        let migrations = self.migrations_by_version().await?;
+        let mut repaired = Vec::new();
+        for AppliedChecksums { version, checksum: stored, down_checksum: stored_down, .. } in Self::applied_checksums(pool).await? {
+            let Some(migration) = migrations.get(&version) else {
+                tracing::warn!(version, "Cannot repair a migration whose file is missing");
+                continue;
+            };
+
+            let checksum = Self::checksum(&migration.up_sql);
+            let down_checksum = Self::down_checksum(migration);
+            if checksum == stored && down_checksum == stored_down {
+                continue;
+            }
 
-            let down_filename = down_files.iter()
-                .find(|filename|{
-                    Self::extract_version_from_down_file(filename) == Some(version)
-                });
+            sqlx::query("UPDATE _schema_migrations SET checksum = $2, down_checksum = $3 WHERE version = $1")
+                .bind(version)
+                .bind(checksum)
+                .bind(down_checksum)
+                .execute(pool)
+                .await?;
 
-            if let Some(down_filename) = down_filename {
-                let down_file_path = format!("{}/{}", self.migrations_dir, down_filename);
-                let sql_content = fs::read_to_string(down_file_path).await?;
+            tracing::info!(version, file = %migration.up_file, "체크섬 갱신");
+            repaired.push(version);
+        }
 
-                let mut tx = pool.begin().await?;
+        Ok(repaired)
+    }
+}
 
-                sqlx::query(&sql_content)
-                    .execute(&mut *tx)
-                    .await?;
+/// Splits a script into statements for running outside a transaction, where a multi-statement
+/// string would be wrapped in an implicit one. Quotes (including `''` and `E'\''` escapes), dollar
+/// quotes and comments are respected.
+fn split_statements(sql: &str) -> Vec<&str> {
+    let bytes = sql.as_bytes();
+    let mut statements = Vec::new();
+    let mut start = 0;
+    let mut i = 0;
+
+    while i < bytes.len() {
+        match bytes[i] {
+            b'\'' | b'"' => {
+                let quote = bytes[i];
+                // Only `E'...'` strings treat backslash as an escape; the `E` must not end an identifier.
+                let escapes = quote == b'\''
+                    && i > 0
+                    && bytes[i - 1].eq_ignore_ascii_case(&b'e')
+                    && (i < 2 || !(bytes[i - 2].is_ascii_alphanumeric() || bytes[i - 2] == b'_'));
+                i += 1;
+                while let Some(&c) = bytes.get(i) {
+                    // A backslash escape or a doubled quote: skip both bytes.
+                    if (escapes && c == b'\\') || (c == quote && bytes.get(i + 1) == Some(&quote)) {
+                        i += 2;
+                    } else if c == quote {
+                        break;
+                    } else {
+                        i += 1;
+                    }
+                }
+            }
+            b'-' if bytes.get(i + 1) == Some(&b'-') => {
+                while i < bytes.len() && bytes[i] != b'\n' {
+                    i += 1;
+                }
+            }
+            b'/' if bytes.get(i + 1) == Some(&b'*') => {
+                i = sql[i + 2..].find("*/").map_or(bytes.len(), |end| i + 2 + end + 1);
+            }
+            b'$' => {
+                if let Some(len) = sql[i + 1..].find('$')
+                    && sql[i + 1..i + 1 + len].chars().all(|c| c.is_alphanumeric() || c == '_') {
+                    let tag = &sql[i..i + len + 2];
+                    i = sql[i + tag.len()..].find(tag).map_or(bytes.len(), |end| i + tag.len() + end + tag.len() - 1);
+                }
+            }
+            b';' => {
+                statements.push(&sql[start..=i]);
+                start = i + 1;
+            }
+            _ => {}
+        }
+        i += 1;
+    }
+    statements.push(&sql[start..]);
+
+    statements.into_iter()
+        .filter(|statement| {
+            statement.lines()
+                .map(str::trim)
+                .any(|line| !line.is_empty() && !line.starts_with("--") && line != ";")
+        })
+        .collect()
+}
 
-                sqlx::query("DELETE FROM _sqlx_migrations WHERE version = $1")
-                    .bind(version)
-                    .execute(&mut *tx)
-                    .await?;
+/// `url` pointing at `database` instead, keeping credentials and libpq query parameters.
+fn with_database(url: &str, database: &str) -> String {
+    let authority_start = url.find("://").map_or(0, |i| i + 3);
+    let path_start = url[authority_start..].find(['/', '?']).map_or(url.len(), |i| authority_start + i);
+    let query_start = url[path_start..].find('?').map_or(url.len(), |i| path_start + i);
 
-                tx.commit().await?;
+    format!("{}/{}{}", &url[..path_start], database, &url[query_start..])
+}
 
-                println!("버전 {} 롤백 완료 ({})", version, down_filename);
-            } else {
-                println!("버전 {}의 down.sql 파일을 찾을 수 없습니다!", version);
-                return Err(AppError::NotFound("down 파일 없음:".to_string()));
+/// Splits the password out of a libpq URL, from either the userinfo or a `password` query parameter,
+/// returning the URL without it and the decoded password.
+fn without_password(url: &str) -> (String, Option<String>) {
+    let authority_start = url.find("://").map_or(0, |i| i + 3);
+    let path_start = url[authority_start..].find(['/', '?']).map_or(url.len(), |i| authority_start + i);
+    let query_start = url[path_start..].find('?').map_or(url.len(), |i| path_start + i);
+    let mut password = None;
+
+    let mut authority = url[authority_start..path_start].to_string();
+    if let Some(at) = authority.rfind('@')
+        && let Some(colon) = authority[..at].find(':') {
+        password = Some(percent_decode(&authority[colon + 1..at]));
+        authority.replace_range(colon..at, "");
+    }
+
+    let mut params = Vec::new();
+    for param in url.get(query_start + 1..).unwrap_or_default().split('&').filter(|param| !param.is_empty()) {
+        match param.strip_prefix("password=") {
+            Some(value) => password = Some(percent_decode(value)),
+            None => params.push(param),
+        }
+    }
+    let query = if params.is_empty() { String::new() } else { format!("?{}", params.join("&")) };
+
+    (format!("{}{}{}{}", &url[..authority_start], authority, &url[path_start..query_start], query), password)
+}
+
+fn percent_decode(value: &str) -> String {
+    let bytes = value.as_bytes();
+    let mut decoded = Vec::with_capacity(bytes.len());
+    let mut i = 0;
+
+    while i < bytes.len() {
+        let hex = value.get(i + 1..i + 3).and_then(|hex| u8::from_str_radix(hex, 16).ok());
+        match (bytes[i], hex) {
+            (b'%', Some(byte)) => {
+                decoded.push(byte);
+                i += 3;
+            }
+            (byte, _) => {
+                decoded.push(byte);
+                i += 1;
             }
         }
+    }
 
-        println!("롤백 완료!");
-        Ok(())
+    String::from_utf8_lossy(&decoded).into_owned()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn splits_on_semicolons_and_keeps_a_trailing_statement() {
+        assert_eq!(
+            split_statements("CREATE TABLE a (id INT);\nCREATE INDEX a_id ON a (id)"),
+            vec!["CREATE TABLE a (id INT);", "\nCREATE INDEX a_id ON a (id)"],
+        );
+    }
+
+    #[test]
+    fn ignores_semicolons_in_quotes() {
+        assert_eq!(
+            split_statements("INSERT INTO a VALUES ('x;y', 'it''s; fine');\nSELECT \"odd;name\" FROM a;"),
+            vec!["INSERT INTO a VALUES ('x;y', 'it''s; fine');", "\nSELECT \"odd;name\" FROM a;"],
+        );
+    }
+
+    #[test]
+    fn honours_backslash_escapes_only_in_e_strings() {
+        assert_eq!(
+            split_statements(r"SELECT E'it\'s; fine', e'\\';SELECT 'C:\';"),
+            vec![r"SELECT E'it\'s; fine', e'\\';", r"SELECT 'C:\';"],
+        );
+        // `some_type'...'` is not an escape string just because the identifier ends in `e`.
+        assert_eq!(split_statements(r"SELECT some_type'\';SELECT 1;"), vec![r"SELECT some_type'\';", "SELECT 1;"]);
+    }
+
+    #[test]
+    fn ignores_semicolons_in_dollar_quoted_bodies() {
+        let function = "CREATE FUNCTION f() RETURNS INT AS $$ BEGIN RETURN 1; END; $$ LANGUAGE plpgsql;";
+        let tagged = "DO $body$ BEGIN PERFORM '$$;'; END $body$;";
+        assert_eq!(split_statements(&format!("{}\n{}", function, tagged)), vec![function.to_string(), format!("\n{}", tagged)]);
     }
-}
\ No newline at end of file
+
+    #[test]
+    fn ignores_semicolons_in_comments_and_drops_comment_only_tails() {
+        assert_eq!(
+            split_statements("-- setup; part one\nSELECT 1 /* not; here */;\nSELECT 2;\n-- done;\n"),
+            vec!["-- setup; part one\nSELECT 1 /* not; here */;", "\nSELECT 2;"],
+        );
+    }
+
+    #[test]
+    fn strips_passwords_from_urls() {
+        assert_eq!(
+            without_password("postgres://app:s%40cret@db:5432/scratch?sslmode=require"),
+            ("postgres://app@db:5432/scratch?sslmode=require".to_string(), Some("s@cret".to_string())),
+        );
+        assert_eq!(
+            without_password("postgres://app@db/scratch?password=p%26w&sslmode=require"),
+            ("postgres://app@db/scratch?sslmode=require".to_string(), Some("p&w".to_string())),
+        );
+        assert_eq!(without_password("postgres://app@db/scratch"), ("postgres://app@db/scratch".to_string(), None));
+    }
+
+    #[test]
+    fn no_transaction_directive_must_lead_the_script() {
+        assert!(MigrationManager::runs_in_transaction("CREATE TABLE a (id INT);"));
+        assert!(!MigrationManager::runs_in_transaction("-- migrate:no-transaction\nCREATE INDEX CONCURRENTLY a_id ON a (id);"));
+        assert!(!MigrationManager::runs_in_transaction("\n-- Builds the index online.\n  -- migrate:no-transaction\nCREATE INDEX CONCURRENTLY a_id ON a (id);"));
+        assert!(MigrationManager::runs_in_transaction("CREATE TABLE a (id INT);\n-- migrate:no-transaction\n"));
+    }
+}
@@ -0,0 +1,173 @@
+use crate::error::Error as AppError;
+use include_dir::{include_dir, Dir};
+use std::{collections::BTreeMap, path::PathBuf};
+
+/// Where the migrations were compiled in from, and where `migrate new` writes by default.
+pub const EMBEDDED_MIGRATIONS_DIR: &str = concat!(env!("CARGO_MANIFEST_DIR"), "/src/database/migrations");
+
+static EMBEDDED_MIGRATIONS: Dir<'static> = include_dir!("$CARGO_MANIFEST_DIR/src/database/migrations");
+
+/// A numbered migration with its scripts, parsed from `{version}_{description}.up.sql`
+/// and the matching `.down.sql`.
+#[derive(Debug, Clone)]
+pub struct Migration {
+    pub version: i64,
+    pub description: String,
+    /// File name of the up script, e.g. `001_create_users.up.sql`.
+    pub up_file: String,
+    pub up_sql: String,
+    pub down_sql: Option<String>,
+}
+
+/// A squashed schema snapshot at `version`, applied instead of the individual migrations up to
+/// `version` on a fresh database. Parsed from `{version}_{description}.baseline.sql`.
+#[derive(Debug, Clone)]
+pub struct Baseline {
+    pub version: i64,
+    pub file: String,
+    pub sql: String,
+}
+
+impl Migration {
+    pub fn down_file(&self) -> String {
+        self.up_file.replace(".up.sql", ".down.sql")
+    }
+}
+
+/// Where `MigrationManager` reads migration scripts from.
+#[derive(Debug, Clone)]
+pub enum MigrationSource {
+    /// Compiled into the binary, so deployments don't need the source tree.
+    Embedded,
+    /// Read from disk on every call; for development against files being edited.
+    Filesystem(PathBuf),
+}
+
+impl MigrationSource {
+    /// Directory that holds (or, for `Embedded`, held at compile time) the migration files.
+    pub fn dir(&self) -> PathBuf {
+        match self {
+            MigrationSource::Embedded => PathBuf::from(EMBEDDED_MIGRATIONS_DIR),
+            MigrationSource::Filesystem(dir) => dir.clone(),
+        }
+    }
+
+    /// Every migration ordered by version.
+    pub async fn load(&self) -> Result<Vec<Migration>, AppError> {
+        Self::parse(self.files().await?)
+    }
+
+    /// The highest-versioned baseline, if any.
+    pub async fn load_baseline(&self) -> Result<Option<Baseline>, AppError> {
+        Ok(self.files().await?
+            .into_iter()
+            .filter_map(|(file, sql)| {
+                let version = file.strip_suffix(".baseline.sql")?.split('_').next()?.parse().ok()?;
+                Some(Baseline { version, file, sql })
+            })
+            .max_by_key(|baseline| baseline.version))
+    }
+
+    async fn files(&self) -> Result<Vec<(String, String)>, AppError> {
+        match self {
+            MigrationSource::Embedded => Self::embedded_files(),
+            MigrationSource::Filesystem(dir) => Self::filesystem_files(dir).await,
+        }
+    }
+
+    fn embedded_files() -> Result<Vec<(String, String)>, AppError> {
+        EMBEDDED_MIGRATIONS.files()
+            .filter_map(|file| {
+                let name = file.path().file_name()?.to_str()?.to_string();
+                Some((name, file))
+            })
+            .map(|(name, file)| {
+                let sql = file.contents_utf8()
+                    .ok_or_else(|| AppError::Server(format!("Embedded migration {} is not UTF-8", name)))?;
+                Ok((name, sql.to_string()))
+            })
+            .collect()
+    }
+
+    async fn filesystem_files(dir: &PathBuf) -> Result<Vec<(String, String)>, AppError> {
+        tracing::debug!(?dir, "마이그레이션 파일 검색");
+
+        let mut files = Vec::new();
+        let mut entries = tokio::fs::read_dir(dir).await.inspect_err(|e| {
+            tracing::error!(?dir, error = %e, "디렉토리 읽기 실패");
+        })?;
+
+        while let Some(entry) = entries.next_entry().await? {
+            if !entry.file_type().await?.is_file() {
+                continue;
+            }
+
+            match entry.file_name().into_string() {
+                Ok(name) if name.ends_with(".sql") => {
+                    let sql = tokio::fs::read_to_string(entry.path()).await?;
+                    files.push((name, sql));
+                }
+                Ok(_) => {}
+                Err(name) => tracing::warn!(file_name = ?name, "파일명을 문자열로 변환 실패"),
+            }
+        }
+
+        Ok(files)
+    }
+
+    fn parse(files: Vec<(String, String)>) -> Result<Vec<Migration>, AppError> {
+        let mut migrations = BTreeMap::new();
+        let mut down_scripts = BTreeMap::new();
+
+        for (name, sql) in files {
+            if name.ends_with(".up.sql") {
+                let Some((version, description)) = Self::parse_up_file_name(&name) else {
+                    tracing::warn!(file = %name, "Skipping migration without a numeric version prefix");
+                    continue;
+                };
+
+                let migration = Migration { version, description, up_file: name, up_sql: sql, down_sql: None };
+                if let Some(existing) = migrations.insert(version, migration) {
+                    return Err(AppError::Server(format!(
+                        "Duplicate migration version {} ({})", version, existing.up_file,
+                    )));
+                }
+            } else if let Some(version) = Self::parse_down_file_name(&name) {
+                down_scripts.insert(version, sql);
+            }
+        }
+
+        for (version, sql) in down_scripts {
+            match migrations.get_mut(&version) {
+                Some(migration) => migration.down_sql = Some(sql),
+                None => tracing::warn!(version, "down.sql without a matching up.sql"),
+            }
+        }
+
+        Ok(migrations.into_values().collect())
+    }
+
+    fn parse_up_file_name(filename: &str) -> Option<(i64, String)> {
+        let without_extension = filename.strip_suffix(".up.sql")?;
+
+        let mut parts = without_extension.splitn(2, '_');
+
+        let version = parts.next()?.parse::<i64>().ok()?;
+
+        let description = match parts.next() {
+            Some(desc_part) => desc_part.replace('_', " ").trim().to_lowercase(),
+            None => "migration".to_string(),
+        };
+
+        Some((version, description))
+    }
+
+    fn parse_down_file_name(filename: &str) -> Option<i64> {
+        filename
+            .strip_suffix(".down.sql")?
+            .split('_')
+            .next()?
+            .parse()
+            .ok()
+    }
+}
@@ -0,0 +1,6 @@
+ALTER TABLE users ADD COLUMN deleted_at TIMESTAMPTZ;
+ALTER TABLE users ADD COLUMN deleted_by UUID REFERENCES users(id) ON DELETE SET NULL;
+
+UPDATE users SET deleted_at = updated_at WHERE is_deleted = TRUE;
+
+ALTER TABLE users ADD CONSTRAINT users_deleted_at_check CHECK (is_deleted = (deleted_at IS NOT NULL));
@@ -2,4 +2,6 @@ pub mod models;
 pub mod repositories;
 pub mod services;
 pub mod apply_migration;
+pub mod migration_source;
+pub mod seed;
 pub mod utils;
\ No newline at end of file
@@ -0,0 +1,15 @@
+use time::OffsetDateTime;
+use uuid::Uuid;
+use sqlx::FromRow;
+
+#[derive(Debug, FromRow)]
+pub struct DbRefreshToken {
+    pub id: Uuid,
+    pub user_id: Uuid,
+    pub family_id: Uuid,
+    pub token_hash: Vec<u8>,
+    pub expires_at: OffsetDateTime,
+    pub used_at: Option<OffsetDateTime>,
+    pub revoked_at: Option<OffsetDateTime>,
+    pub created_at: OffsetDateTime,
+}
@@ -10,6 +10,18 @@ pub struct DbUser {
     pub password_hash: String,
     pub is_deleted: bool,
     pub internal_notes: Option<String>,
+    pub token_version: i32,
+    pub deleted_at: Option<OffsetDateTime>,
+    /// The user who soft-deleted this one; cleared again if that user is purged.
+    pub deleted_by: Option<Uuid>,
     pub created_at: OffsetDateTime,
     pub updated_at: OffsetDateTime,
-}
\ No newline at end of file
+}
+
+/// A user together with the names of their roles and the permissions those roles grant in `role_permissions`.
+#[derive(Debug)]
+pub struct DbUserWithRoles {
+    pub user: DbUser,
+    pub role_names: Vec<String>,
+    pub permission_names: Vec<String>,
+}
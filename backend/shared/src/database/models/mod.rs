@@ -1,2 +1,3 @@
 pub mod db_user;
-pub mod db_migration;
\ No newline at end of file
+pub mod db_migration;
+pub mod db_refresh_token;
\ No newline at end of file
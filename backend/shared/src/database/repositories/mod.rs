@@ -1 +1,3 @@
-pub mod user_repository;
\ No newline at end of file
+pub mod user_repository;
+pub mod refresh_token_repository;
+pub mod revoked_token_repository;
\ No newline at end of file
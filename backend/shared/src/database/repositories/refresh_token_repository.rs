@@ -0,0 +1,92 @@
+use crate::{
+    database::models::db_refresh_token::DbRefreshToken,
+};
+use sqlx::PgPool;
+use time::OffsetDateTime;
+use uuid::Uuid;
+
+#[derive(Clone)]
+pub struct RefreshTokenRepository {
+    pool: PgPool,
+}
+
+impl RefreshTokenRepository {
+
+    pub fn new(pool: PgPool) -> Self {
+        Self { pool }
+    }
+
+    #[tracing::instrument(level = "debug", skip(self, token_hash), err(level = "debug"))]
+    pub async fn create(
+        &self,
+        user_id: Uuid,
+        family_id: Uuid,
+        token_hash: &[u8],
+        expires_at: OffsetDateTime,
+    ) -> Result<DbRefreshToken, sqlx::Error> {
+        sqlx::query_as::<_, DbRefreshToken>(
+            "INSERT INTO refresh_tokens (user_id, family_id, token_hash, expires_at)
+             VALUES ($1, $2, $3, $4)
+             RETURNING *"
+        )
+        .bind(user_id)
+        .bind(family_id)
+        .bind(token_hash)
+        .bind(expires_at)
+        .fetch_one(&self.pool)
+        .await
+    }
+
+    #[tracing::instrument(level = "debug", skip(self, token_hash), err(level = "debug"))]
+    pub async fn find_by_hash(&self, token_hash: &[u8]) -> Result<Option<DbRefreshToken>, sqlx::Error> {
+        sqlx::query_as::<_, DbRefreshToken>(
+            "SELECT * FROM refresh_tokens WHERE token_hash = $1"
+        )
+        .bind(token_hash)
+        .fetch_optional(&self.pool)
+        .await
+    }
+
+    /// Atomically marks a live token as used. Returns `None` if the token is unknown, already used,
+    /// revoked or expired, so two concurrent refreshes with the same token can't both succeed.
+    #[tracing::instrument(level = "debug", skip(self, token_hash), err(level = "debug"))]
+    pub async fn consume(&self, token_hash: &[u8]) -> Result<Option<DbRefreshToken>, sqlx::Error> {
+        sqlx::query_as::<_, DbRefreshToken>(
+            "UPDATE refresh_tokens SET used_at = NOW()
+             WHERE token_hash = $1
+               AND used_at IS NULL
+               AND revoked_at IS NULL
+               AND expires_at > NOW()
+             RETURNING *"
+        )
+        .bind(token_hash)
+        .fetch_optional(&self.pool)
+        .await
+    }
+
+    #[tracing::instrument(level = "debug", skip(self), err(level = "debug"))]
+    pub async fn revoke_family(&self, family_id: Uuid) -> Result<u64, sqlx::Error> {
+        let result = sqlx::query(
+            "UPDATE refresh_tokens SET revoked_at = NOW()
+             WHERE family_id = $1 AND revoked_at IS NULL"
+        )
+        .bind(family_id)
+        .execute(&self.pool)
+        .await?;
+
+        Ok(result.rows_affected())
+    }
+
+    #[tracing::instrument(level = "debug", skip(self), err(level = "debug"))]
+    pub async fn revoke_all_for_user(&self, user_id: Uuid) -> Result<u64, sqlx::Error> {
+        let result = sqlx::query(
+            "UPDATE refresh_tokens SET revoked_at = NOW()
+             WHERE user_id = $1 AND revoked_at IS NULL"
+        )
+        .bind(user_id)
+        .execute(&self.pool)
+        .await?;
+
+        Ok(result.rows_affected())
+    }
+}
@@ -0,0 +1,46 @@
+use sqlx::PgPool;
+use time::OffsetDateTime;
+use uuid::Uuid;
+
+/// Denylist of access token `jti`s that were logged out before they expired.
+#[derive(Clone)]
+pub struct RevokedTokenRepository {
+    pool: PgPool,
+}
+
+impl RevokedTokenRepository {
+
+    pub fn new(pool: PgPool) -> Self {
+        Self { pool }
+    }
+
+    /// Adds `jti` to the denylist and drops entries whose token has expired anyway.
+    #[tracing::instrument(level = "debug", skip(self), err(level = "debug"))]
+    pub async fn revoke(&self, jti: &str, user_id: Uuid, expires_at: OffsetDateTime) -> Result<(), sqlx::Error> {
+        sqlx::query(
+            "INSERT INTO revoked_tokens (jti, user_id, expires_at) VALUES ($1, $2, $3)
+             ON CONFLICT (jti) DO NOTHING"
+        )
+        .bind(jti)
+        .bind(user_id)
+        .bind(expires_at)
+        .execute(&self.pool)
+        .await?;
+
+        sqlx::query("DELETE FROM revoked_tokens WHERE expires_at < NOW()")
+            .execute(&self.pool)
+            .await?;
+
+        Ok(())
+    }
+
+    #[tracing::instrument(level = "debug", skip(self), err(level = "debug"))]
+    pub async fn is_revoked(&self, jti: &str) -> Result<bool, sqlx::Error> {
+        sqlx::query_scalar(
+            "SELECT EXISTS(SELECT 1 FROM revoked_tokens WHERE jti = $1)"
+        )
+        .bind(jti)
+        .fetch_one(&self.pool)
+        .await
+    }
+}
@@ -1,9 +1,38 @@
 use crate::{
-    database::models::db_user::DbUser,
+    database::models::db_user::{DbUser, DbUserWithRoles},
 };
-use sqlx::PgPool;
+use sqlx::{PgPool, Postgres, QueryBuilder, Transaction};
 use uuid::Uuid;
 
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum RoleRevocation {
+    Revoked,
+    NotAssigned,
+    /// The user is the only remaining holder of the protected role.
+    LastHolder,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum UserDeletion {
+    Deleted,
+    NotFound,
+    /// The user is the only remaining holder of the protected role.
+    LastHolder,
+}
+
+/// Profile fields to change. `None` leaves the column untouched.
+#[derive(Debug, Clone, Default)]
+pub struct UserChanges {
+    pub username: Option<String>,
+    pub email: Option<String>,
+}
+
+impl UserChanges {
+    pub fn is_empty(&self) -> bool {
+        self.username.is_none() && self.email.is_none()
+    }
+}
+
 #[derive(Clone)]
 pub struct UserRepository{
     pool: PgPool,
@@ -15,6 +44,7 @@ impl UserRepository {
         Self { pool }
     }
 
+    #[tracing::instrument(level = "debug", skip(self), err(level = "debug"))]
     pub async fn find_by_id(&self, id: &str) -> Result<Option<DbUser>, sqlx::Error> {
 
         let uuid_id = Uuid::parse_str(id)
@@ -28,6 +58,7 @@ impl UserRepository {
         .await
     }
 
+    #[tracing::instrument(level = "debug", skip(self), err(level = "debug"))]
     pub async fn find_by_ids(&self, ids: &[&str]) -> Result<Vec<DbUser>, sqlx::Error> {
         let numeric_ids: Vec<Uuid> = ids.iter()
             .filter_map(|id| id.parse().ok())
@@ -47,55 +78,284 @@ impl UserRepository {
         Ok(users)
     }
 
+    /// Looks up by email if the input contains `@`, otherwise by username, so one input can't match two users.
+    #[tracing::instrument(level = "debug", skip(self), err(level = "debug"))]
+    pub async fn find_by_username_or_email(&self, username_or_email: &str) -> Result<Option<DbUser>, sqlx::Error> {
+        let query = if username_or_email.contains('@') {
+            "SELECT * FROM users WHERE email = $1 AND is_deleted = false"
+        } else {
+            "SELECT * FROM users WHERE username = $1 AND is_deleted = false"
+        };
+
+        sqlx::query_as::<_, DbUser>(query)
+            .bind(username_or_email)
+            .fetch_optional(&self.pool)
+            .await
+    }
+
+    #[tracing::instrument(level = "debug", skip(self, password_hash), err(level = "debug"))]
+    pub async fn update_password_hash(&self, id: Uuid, password_hash: &str) -> Result<(), sqlx::Error> {
+        sqlx::query("UPDATE users SET password_hash = $1 WHERE id = $2 AND is_deleted = false")
+            .bind(password_hash)
+            .bind(id)
+            .execute(&self.pool)
+            .await?;
+
+        Ok(())
+    }
+
+    /// Sets only the columns present in `changes`. Returns `None` if the user doesn't exist or is deleted.
+    #[tracing::instrument(level = "debug", skip(self), err(level = "debug"))]
+    pub async fn update(&self, id: Uuid, changes: &UserChanges) -> Result<Option<DbUser>, sqlx::Error> {
+        if changes.is_empty() {
+            return self.find_by_id(&id.to_string()).await;
+        }
+
+        let mut query = QueryBuilder::<Postgres>::new("UPDATE users SET ");
+        let mut columns = query.separated(", ");
+        if let Some(username) = &changes.username {
+            columns.push("username = ").push_bind_unseparated(username);
+        }
+        if let Some(email) = &changes.email {
+            columns.push("email = ").push_bind_unseparated(email);
+        }
+        query.push(" WHERE id = ").push_bind(id).push(" AND is_deleted = false RETURNING *");
+
+        query.build_query_as::<DbUser>()
+            .fetch_optional(&self.pool)
+            .await
+    }
+
+    /// Bumps `token_version`, invalidating every access token issued before the call.
+    #[tracing::instrument(level = "debug", skip(self), err(level = "debug"))]
+    pub async fn increment_token_version(&self, id: Uuid) -> Result<Option<i32>, sqlx::Error> {
+        sqlx::query_scalar(
+            "UPDATE users SET token_version = token_version + 1 WHERE id = $1 AND is_deleted = false RETURNING token_version"
+        )
+        .bind(id)
+        .fetch_optional(&self.pool)
+        .await
+    }
+
+    /// Inserts the user and grants them `default_role` in the same transaction.
+    #[tracing::instrument(level = "debug", skip(self, password_hash), err(level = "debug"))]
     pub async fn create(
         &self,
         username: &str,
         email: &str,
-        password_hash: &str
+        password_hash: &str,
+        default_role: &str,
     ) -> Result<DbUser, sqlx::Error> {
-        sqlx::query_as::<_, DbUser>(
-            "INSERT INTO users (username, email, password_hash) VALUES ($1, $2, $3) RETURNING id, username, email, password_hash, is_deleted, internal_notes, created_at, updated_at"
+        let mut tx = self.pool.begin().await?;
+
+        let db_user = sqlx::query_as::<_, DbUser>(
+            "INSERT INTO users (username, email, password_hash) VALUES ($1, $2, $3) RETURNING id, username, email, password_hash, is_deleted, internal_notes, token_version, deleted_at, deleted_by, created_at, updated_at"
         )
         .bind(username)
         .bind(email)
-        .bind(password_hash) // 실제 hash기능 나중에 추가하기
-        .fetch_one(&self.pool)
-        .await
+        .bind(password_hash)
+        .fetch_one(&mut *tx)
+        .await?;
+
+        sqlx::query(
+            "INSERT INTO user_roles (user_id, role_id) SELECT $1, id FROM roles WHERE name = $2"
+        )
+        .bind(db_user.id)
+        .bind(default_role)
+        .execute(&mut *tx)
+        .await?;
+
+        tx.commit().await?;
+
+        Ok(db_user)
     }
 
+    #[tracing::instrument(level = "debug", skip(self), err(level = "debug"))]
     pub async fn find_all(&self) -> Result<Vec<DbUser>, sqlx::Error> {
-        sqlx::query_as::<_, DbUser>("SELECT * FROM users")
+        sqlx::query_as::<_, DbUser>("SELECT * FROM users WHERE is_deleted = false")
             .fetch_all(&self.pool)
             .await
     }
 
+    #[tracing::instrument(level = "debug", skip(self), err(level = "debug"))]
     pub async fn find_roles_by_id(&self, user_id: &str) -> Result<Vec<String>, sqlx::Error> {
         let uuid = Uuid::parse_str(user_id)
             .map_err(|e| sqlx::Error::Decode(Box::new(e)))?;
 
-        let role_names = sqlx::query!(
+        sqlx::query_scalar(
             "SELECT r.name
              FROM user_roles ur
              JOIN roles r ON ur.role_id = r.id
-             WHERE ur.user_id = $1",
-             uuid
-            )
-            .fetch_all(&self.pool)
-            .await?
-            .into_iter()
-            .map(|row| row.name)
-            .collect::<Vec<String>>();
+             JOIN users u ON ur.user_id = u.id
+             WHERE ur.user_id = $1 AND u.is_deleted = false"
+        )
+        .bind(uuid)
+        .fetch_all(&self.pool)
+        .await
+    }
+
+    #[tracing::instrument(level = "debug", skip(self), err(level = "debug"))]
+    pub async fn find_permissions_by_id(&self, user_id: &str) -> Result<Vec<String>, sqlx::Error> {
+        let uuid = Uuid::parse_str(user_id)
+            .map_err(|e| sqlx::Error::Decode(Box::new(e)))?;
+
+        sqlx::query_scalar(
+            "SELECT DISTINCT p.name
+             FROM user_roles ur
+             JOIN role_permissions rp ON rp.role_id = ur.role_id
+             JOIN permissions p ON rp.permission_id = p.id
+             JOIN users u ON ur.user_id = u.id
+             WHERE ur.user_id = $1 AND u.is_deleted = false"
+        )
+        .bind(uuid)
+        .fetch_all(&self.pool)
+        .await
+    }
+
+    /// Grants `role_name`. Returns `false` if the user already had it or the role doesn't exist.
+    #[tracing::instrument(level = "debug", skip(self), err(level = "debug"))]
+    pub async fn assign_role(&self, user_id: Uuid, role_name: &str) -> Result<bool, sqlx::Error> {
+        let result = sqlx::query(
+            "INSERT INTO user_roles (user_id, role_id)
+             SELECT u.id, r.id FROM users u, roles r
+             WHERE u.id = $1 AND u.is_deleted = false AND r.name = $2
+             ON CONFLICT DO NOTHING"
+        )
+        .bind(user_id)
+        .bind(role_name)
+        .execute(&self.pool)
+        .await?;
+
+        Ok(result.rows_affected() > 0)
+    }
+
+    /// Removes `role_name` from the user unless that would leave no active user holding `protected_role`.
+    /// The holders of `protected_role` are locked first so concurrent revocations can't both pass the check.
+    #[tracing::instrument(level = "debug", skip(self), err(level = "debug"))]
+    pub async fn revoke_role(
+        &self,
+        user_id: Uuid,
+        role_name: &str,
+        protected_role: &str,
+    ) -> Result<RoleRevocation, sqlx::Error> {
+        let mut tx = self.pool.begin().await?;
+
+        if role_name == protected_role && Self::lock_role_holders(&mut tx, protected_role).await? == [user_id] {
+            return Ok(RoleRevocation::LastHolder);
+        }
+
+        let result = sqlx::query(
+            "DELETE FROM user_roles
+             WHERE user_id = (SELECT id FROM users WHERE id = $1 AND is_deleted = false)
+               AND role_id = (SELECT id FROM roles WHERE name = $2)"
+        )
+        .bind(user_id)
+        .bind(role_name)
+        .execute(&mut *tx)
+        .await?;
+
+        tx.commit().await?;
+
+        if result.rows_affected() > 0 {
+            Ok(RoleRevocation::Revoked)
+        } else {
+            Ok(RoleRevocation::NotAssigned)
+        }
+    }
+
+    /// Marks the user deleted unless they are the last active holder of `protected_role`. Their access
+    /// tokens are invalidated by bumping `token_version` and their refresh tokens are revoked, so a later
+    /// restore doesn't bring old sessions back.
+    #[tracing::instrument(level = "debug", skip(self), err(level = "debug"))]
+    pub async fn soft_delete(
+        &self,
+        user_id: Uuid,
+        deleted_by: Uuid,
+        protected_role: &str,
+    ) -> Result<UserDeletion, sqlx::Error> {
+        let mut tx = self.pool.begin().await?;
+
+        if Self::lock_role_holders(&mut tx, protected_role).await? == [user_id] {
+            return Ok(UserDeletion::LastHolder);
+        }
 
-        Ok(role_names)
+        let result = sqlx::query(
+            "UPDATE users
+             SET is_deleted = true, deleted_at = NOW(), deleted_by = $2, token_version = token_version + 1
+             WHERE id = $1 AND is_deleted = false"
+        )
+        .bind(user_id)
+        .bind(deleted_by)
+        .execute(&mut *tx)
+        .await?;
+
+        if result.rows_affected() == 0 {
+            return Ok(UserDeletion::NotFound);
+        }
+
+        sqlx::query(
+            "UPDATE refresh_tokens SET revoked_at = NOW()
+             WHERE user_id = $1 AND revoked_at IS NULL"
+        )
+        .bind(user_id)
+        .execute(&mut *tx)
+        .await?;
+
+        tx.commit().await?;
+
+        Ok(UserDeletion::Deleted)
+    }
+
+    /// Undoes `soft_delete`. Returns `None` if the user doesn't exist or isn't deleted.
+    #[tracing::instrument(level = "debug", skip(self), err(level = "debug"))]
+    pub async fn restore(&self, user_id: Uuid) -> Result<Option<DbUser>, sqlx::Error> {
+        sqlx::query_as::<_, DbUser>(
+            "UPDATE users SET is_deleted = false, deleted_at = NULL, deleted_by = NULL
+             WHERE id = $1 AND is_deleted = true
+             RETURNING *"
+        )
+        .bind(user_id)
+        .fetch_optional(&self.pool)
+        .await
+    }
+
+    /// Permanently removes a soft-deleted user along with their roles and tokens.
+    /// Returns `false` if the user doesn't exist or hasn't been soft-deleted.
+    #[tracing::instrument(level = "debug", skip(self), err(level = "debug"))]
+    pub async fn purge(&self, user_id: Uuid) -> Result<bool, sqlx::Error> {
+        let result = sqlx::query("DELETE FROM users WHERE id = $1 AND is_deleted = true")
+            .bind(user_id)
+            .execute(&self.pool)
+            .await?;
+
+        Ok(result.rows_affected() > 0)
+    }
+
+    /// Active holders of `role_name`. Both their `user_roles` and `users` rows stay locked until `tx` ends,
+    /// so a concurrent revocation or deletion can't also conclude that someone else still holds the role.
+    async fn lock_role_holders(tx: &mut Transaction<'_, Postgres>, role_name: &str) -> Result<Vec<Uuid>, sqlx::Error> {
+        sqlx::query_scalar(
+            "SELECT ur.user_id
+             FROM user_roles ur
+             JOIN roles r ON ur.role_id = r.id
+             JOIN users u ON ur.user_id = u.id
+             WHERE r.name = $1 AND u.is_deleted = false
+             FOR UPDATE OF ur, u"
+        )
+        .bind(role_name)
+        .fetch_all(&mut **tx)
+        .await
     }
 
-    pub async fn find_user_with_roles(&self, user_id: &str) -> Result<Option<(DbUser, Vec<String>)>, sqlx::Error> {
+    #[tracing::instrument(level = "debug", skip(self), err(level = "debug"))]
+    pub async fn find_user_with_roles(&self, user_id: &str) -> Result<Option<DbUserWithRoles>, sqlx::Error> {
         let user = self.find_by_id(user_id).await?;
 
         if let Some(user) = user {
             let role_names = self.find_roles_by_id(user_id).await?;
+            let permission_names = self.find_permissions_by_id(user_id).await?;
 
-            Ok(Some((user, role_names)))
+            Ok(Some(DbUserWithRoles { user, role_names, permission_names }))
         } else {
             Ok(None)
         }
@@ -0,0 +1,106 @@
+use sqlx::PgPool;
+use crate::{
+    auth::{Role, Permission},
+    error::Error as AppError,
+};
+
+/// What `Seeder::seed_roles_and_permissions` changed and which rows it could not account for.
+#[derive(Debug, Default)]
+pub struct SeedReport {
+    pub roles_created: Vec<String>,
+    pub permissions_created: Vec<String>,
+    /// Rows in `roles` whose name doesn't match any `Role` variant.
+    pub unknown_roles: Vec<String>,
+    /// Rows in `permissions` whose name doesn't match any `Permission` variant.
+    pub unknown_permissions: Vec<String>,
+}
+
+pub struct Seeder;
+
+impl Seeder {
+    /// Upserts every `Role` and `Permission` variant with its description. Default grants from
+    /// `Role::default_permissions` are only written when a role is created, so grants changed by
+    /// operators afterwards survive restarts.
+    pub async fn seed_roles_and_permissions(pool: &PgPool) -> Result<SeedReport, AppError> {
+        let mut report = SeedReport::default();
+        let mut tx = pool.begin().await?;
+
+        for permission in Permission::ALL {
+            let inserted: bool = sqlx::query_scalar(
+                "INSERT INTO permissions (name, description) VALUES ($1, $2)
+                 ON CONFLICT (name) DO UPDATE SET description = EXCLUDED.description
+                 RETURNING (xmax = 0)"
+            )
+            .bind(permission.as_str())
+            .bind(permission.description())
+            .fetch_one(&mut *tx)
+            .await?;
+
+            if inserted {
+                report.permissions_created.push(permission.to_string());
+            }
+        }
+
+        for role in Role::ALL {
+            let inserted: bool = sqlx::query_scalar(
+                "INSERT INTO roles (name, description) VALUES ($1, $2)
+                 ON CONFLICT (name) DO UPDATE SET description = EXCLUDED.description
+                 RETURNING (xmax = 0)"
+            )
+            .bind(role.as_str())
+            .bind(role.description())
+            .fetch_one(&mut *tx)
+            .await?;
+
+            if inserted {
+                let permission_names: Vec<&str> = role.default_permissions()
+                    .iter()
+                    .map(|permission| permission.as_str())
+                    .collect();
+
+                sqlx::query(
+                    "INSERT INTO role_permissions (role_id, permission_id)
+                     SELECT r.id, p.id FROM roles r, permissions p
+                     WHERE r.name = $1 AND p.name = ANY($2)
+                     ON CONFLICT DO NOTHING"
+                )
+                .bind(role.as_str())
+                .bind(&permission_names)
+                .execute(&mut *tx)
+                .await?;
+
+                report.roles_created.push(role.to_string());
+            }
+        }
+
+        let role_names: Vec<&str> = Role::ALL.iter().map(|role| role.as_str()).collect();
+        report.unknown_roles = sqlx::query_scalar(
+            "SELECT name FROM roles WHERE NOT (name = ANY($1)) ORDER BY name"
+        )
+        .bind(&role_names)
+        .fetch_all(&mut *tx)
+        .await?;
+
+        let permission_names: Vec<&str> = Permission::ALL.iter().map(|permission| permission.as_str()).collect();
+        report.unknown_permissions = sqlx::query_scalar(
+            "SELECT name FROM permissions WHERE NOT (name = ANY($1)) ORDER BY name"
+        )
+        .bind(&permission_names)
+        .fetch_all(&mut *tx)
+        .await?;
+
+        tx.commit().await?;
+
+        if !report.roles_created.is_empty() || !report.permissions_created.is_empty() {
+            tracing::info!(roles = ?report.roles_created, permissions = ?report.permissions_created, "역할/권한 생성");
+        }
+        if !report.unknown_roles.is_empty() {
+            tracing::warn!(roles = ?report.unknown_roles, "Roles in the database without a matching Role variant");
+        }
+        if !report.unknown_permissions.is_empty() {
+            tracing::warn!(permissions = ?report.unknown_permissions, "Permissions in the database without a matching Permission variant");
+        }
+
+        Ok(report)
+    }
+}
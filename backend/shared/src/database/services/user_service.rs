@@ -1,38 +1,65 @@
 use crate::{
-    error::Error,
+    error::{Error, FieldError},
+    auth::{CurrentUser, PasswordService, Permission, Role},
     models::user::UserProfile,
-    database::repositories::user_repository::UserRepository,
+    database::repositories::user_repository::{UserRepository, RoleRevocation, UserChanges, UserDeletion},
 };
+use uuid::Uuid;
+
+/// Length of the `username` and `email` columns.
+const MAX_FIELD_LENGTH: usize = 255;
 
 #[derive(Clone)]
 pub struct UserService {
     user_repo: UserRepository,
+    password_service: PasswordService,
 }
 
 impl UserService {
-    pub fn new(user_repo: UserRepository) -> Self {
-        Self { user_repo }
+    pub fn new(user_repo: UserRepository, password_service: PasswordService) -> Self {
+        Self { user_repo, password_service }
     }
 
     pub async fn create(
         &self,
         username: &str,
         email: &str,
-        password_hash: &str
+        password: &str
+    ) -> Result<UserProfile, Error> {
+        self.create_with_role(username, email, password, Role::User).await
+    }
+
+    /// Like `create`, but grants `role` instead of the default `User` role. Used to bootstrap the first admin.
+    pub async fn create_with_role(
+        &self,
+        username: &str,
+        email: &str,
+        password: &str,
+        role: Role,
     ) -> Result<UserProfile, Error> {
-        let db_user = self.user_repo.create(&username, &email, &password_hash).await?;
+        let (username, email) = (username.trim(), email.trim());
+        let errors: Vec<FieldError> = [Self::check_username(username), Self::check_email(email)]
+            .into_iter()
+            .flatten()
+            .collect();
+        if !errors.is_empty() {
+            return Err(Error::ValidationFailed(errors));
+        }
+
+        let password_hash = self.password_service.hash(password).await?;
+        let db_user = self.user_repo.create(username, email, &password_hash, role.as_str()).await?;
 
         Ok(UserProfile::from(db_user))
     }
 
-    pub async fn find_by_id(&self, id: &str) -> Result<Option<UserProfile>, sqlx::Error> {
+    pub async fn find_by_id(&self, id: &str) -> Result<Option<UserProfile>, Error> {
         let db_user = self.user_repo.find_by_id(id).await?;
         let user_profile = db_user.map(UserProfile::from);
 
         Ok(user_profile)
     }
 
-    pub async fn find_by_ids(&self, ids: &[&str]) -> Result<Vec<UserProfile>, sqlx::Error> {
+    pub async fn find_by_ids(&self, ids: &[&str]) -> Result<Vec<UserProfile>, Error> {
 
         let db_users = self.user_repo.find_by_ids(ids).await?;
         let user_profiles: Vec<UserProfile> = db_users
@@ -43,7 +70,7 @@ impl UserService {
         Ok(user_profiles)
     }
 
-    pub async fn find_all(&self) -> Result<Vec<UserProfile>, sqlx::Error> {
+    pub async fn find_all(&self) -> Result<Vec<UserProfile>, Error> {
         let db_users = self.user_repo.find_all().await?;
         
         let user_profiles: Vec<UserProfile> = db_users
@@ -53,4 +80,144 @@ impl UserService {
 
         Ok(user_profiles)
     }
-}
\ No newline at end of file
+
+    pub async fn find_roles(&self, id: &str) -> Result<Vec<Role>, Error> {
+        let role_names = self.user_repo.find_roles_by_id(id).await?;
+
+        let mut roles: Vec<Role> = role_names.iter()
+            .filter_map(|name| name.parse().ok())
+            .collect();
+        roles.sort_by_key(|role| role.as_str());
+
+        Ok(roles)
+    }
+
+    pub async fn assign_role(&self, id: &str, role: Role) -> Result<(), Error> {
+        let user_id = self.require_user_id(id).await?;
+
+        self.user_repo.assign_role(user_id, role.as_str()).await?;
+
+        Ok(())
+    }
+
+    /// Refuses to remove the Admin role from the last admin.
+    pub async fn revoke_role(&self, id: &str, role: Role) -> Result<(), Error> {
+        let user_id = self.require_user_id(id).await?;
+
+        match self.user_repo.revoke_role(user_id, role.as_str(), Role::Admin.as_str()).await? {
+            RoleRevocation::Revoked | RoleRevocation::NotAssigned => Ok(()),
+            RoleRevocation::LastHolder => Err(Error::Conflict(
+                "Cannot remove the Admin role from the last admin".to_string()
+            )),
+        }
+    }
+
+    /// Applies the fields present in `changes`. Users may edit their own profile; editing someone
+    /// else's requires `UpdateUser`.
+    pub async fn update(&self, current_user: &CurrentUser, id: &str, changes: UserChanges) -> Result<UserProfile, Error> {
+        let user_id = Self::parse_user_id(id)?;
+        if current_user.id != user_id {
+            current_user.require_permission(&Permission::UpdateUser)?;
+        }
+
+        let changes = Self::validate_changes(changes)?;
+        let db_user = self.user_repo.update(user_id, &changes).await?
+            .ok_or(Error::NotFound("User not found".to_string()))?;
+
+        Ok(UserProfile::from(db_user))
+    }
+
+    fn validate_changes(changes: UserChanges) -> Result<UserChanges, Error> {
+        let username = changes.username.map(|username| username.trim().to_string());
+        let email = changes.email.map(|email| email.trim().to_string());
+
+        let errors: Vec<FieldError> = [
+            username.as_deref().and_then(Self::check_username),
+            email.as_deref().and_then(Self::check_email),
+        ]
+        .into_iter()
+        .flatten()
+        .collect();
+        if !errors.is_empty() {
+            return Err(Error::ValidationFailed(errors));
+        }
+
+        Ok(UserChanges { username, email })
+    }
+
+    /// Usernames can't contain `@`, so login can tell them apart from email addresses.
+    fn check_username(username: &str) -> Option<FieldError> {
+        if username.is_empty() {
+            Some(FieldError::new("username", "must not be empty"))
+        } else if username.contains('@') {
+            Some(FieldError::new("username", "must not contain '@'"))
+        } else if username.chars().count() > MAX_FIELD_LENGTH {
+            Some(FieldError::new("username", format!("must be at most {} characters", MAX_FIELD_LENGTH)))
+        } else {
+            None
+        }
+    }
+
+    fn check_email(email: &str) -> Option<FieldError> {
+        if !email.contains('@') {
+            Some(FieldError::new("email", "must be an email address"))
+        } else if email.chars().count() > MAX_FIELD_LENGTH {
+            Some(FieldError::new("email", format!("must be at most {} characters", MAX_FIELD_LENGTH)))
+        } else {
+            None
+        }
+    }
+
+    /// Hides the user from every lookup and ends their sessions. Refuses to delete the last admin.
+    pub async fn soft_delete(&self, current_user: &CurrentUser, id: &str) -> Result<(), Error> {
+        current_user.require_permission(&Permission::DeleteUser)?;
+        let user_id = Self::parse_user_id(id)?;
+
+        match self.user_repo.soft_delete(user_id, current_user.id, Role::Admin.as_str()).await? {
+            UserDeletion::Deleted => Ok(()),
+            UserDeletion::NotFound => Err(Error::NotFound("User not found".to_string())),
+            UserDeletion::LastHolder => Err(Error::Conflict(
+                "Cannot delete the last admin".to_string()
+            )),
+        }
+    }
+
+    pub async fn restore(&self, current_user: &CurrentUser, id: &str) -> Result<UserProfile, Error> {
+        current_user.require_permission(&Permission::DeleteUser)?;
+        let user_id = Self::parse_user_id(id)?;
+
+        let db_user = self.user_repo.restore(user_id).await?
+            .ok_or(Error::NotFound("Deleted user not found".to_string()))?;
+
+        Ok(UserProfile::from(db_user))
+    }
+
+    /// Permanently removes a user. Only soft-deleted users can be purged.
+    pub async fn purge(&self, current_user: &CurrentUser, id: &str) -> Result<(), Error> {
+        current_user.require_permission(&Permission::DeleteUser)?;
+        let user_id = Self::parse_user_id(id)?;
+
+        if self.user_repo.purge(user_id).await? {
+            return Ok(());
+        }
+
+        match self.user_repo.find_by_id(id).await? {
+            Some(_) => Err(Error::Conflict("User must be deleted before it can be purged".to_string())),
+            None => Err(Error::NotFound("User not found".to_string())),
+        }
+    }
+
+    fn parse_user_id(id: &str) -> Result<Uuid, Error> {
+        Uuid::parse_str(id)
+            .map_err(|_| Error::InvalidInput("Invalid user id".to_string()))
+    }
+
+    async fn require_user_id(&self, id: &str) -> Result<Uuid, Error> {
+        Self::parse_user_id(id)?;
+
+        let db_user = self.user_repo.find_by_id(id).await?
+            .ok_or(Error::NotFound("User not found".to_string()))?;
+
+        Ok(db_user.id)
+    }
+}
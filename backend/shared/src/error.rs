@@ -1,6 +1,17 @@
 use thiserror::Error;
-use actix_web::HttpResponse;
-use serde_json::json;
+use actix_web::{
+    http::{header::ContentType, StatusCode},
+    HttpResponse,
+};
+use async_graphql::{
+    extensions::{Extension, ExtensionContext, ExtensionFactory, NextRequest, NextResolve, ResolveInfo},
+    Response, ServerError, ServerResult, Value,
+};
+use serde::Serialize;
+use std::sync::Arc;
+use crate::{metrics::metrics, request_id};
+
+pub const PROBLEM_JSON: &str = "application/problem+json";
 
 #[derive(Error, Debug)]
 pub enum Error {
@@ -13,12 +24,18 @@ pub enum Error {
     #[error("Not found: {0}")]
     NotFound(String),
 
+    #[error("Conflict: {0}")]
+    Conflict(String),
+
     #[error("Database error: {0}")]
-    Database(#[from] sqlx::Error),
+    Database(sqlx::Error),
 
     #[error("Validation error: {0}")]
     Validation(String),
 
+    #[error("Validation failed: {} invalid field(s)", .0.len())]
+    ValidationFailed(Vec<FieldError>),
+
     #[error("IO error: {0}")]
     Io(#[from] std::io::Error),
 
@@ -29,33 +46,229 @@ pub enum Error {
     InvalidInput(String),
 }
 
+/// Every database error passes through here on its way out of a service, so this is where pool
+/// exhaustion is counted.
+impl From<sqlx::Error> for Error {
+    fn from(err: sqlx::Error) -> Self {
+        if matches!(err, sqlx::Error::PoolTimedOut) {
+            metrics().db_pool_acquire_timeouts_total.inc();
+        }
+        Error::Database(err)
+    }
+}
+
+/// A problem with a single input field, reported in the `details` of a 422 response.
+#[derive(Debug, Clone, Serialize)]
+pub struct FieldError {
+    pub field: String,
+    pub message: String,
+}
+
+impl FieldError {
+    pub fn new(field: impl Into<String>, message: impl Into<String>) -> Self {
+        Self { field: field.into(), message: message.into() }
+    }
+}
+
+/// RFC 7807 body served as `application/problem+json`.
+#[derive(Debug, Serialize)]
+pub struct ProblemDetails {
+    #[serde(rename = "type")]
+    pub problem_type: &'static str,
+    pub title: &'static str,
+    pub status: u16,
+    pub detail: String,
+    /// Stable, machine-readable error code. Clients should branch on this rather than `detail`.
+    pub code: &'static str,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub request_id: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub details: Option<Vec<FieldError>>,
+}
+
+impl Error {
+    /// Stable, machine-readable code for the error, one per status we return.
+    pub fn code(&self) -> &'static str {
+        match actix_web::ResponseError::status_code(self) {
+            StatusCode::UNAUTHORIZED => "UNAUTHORIZED",
+            StatusCode::FORBIDDEN => "FORBIDDEN",
+            StatusCode::NOT_FOUND => "NOT_FOUND",
+            StatusCode::CONFLICT => "CONFLICT",
+            StatusCode::UNPROCESSABLE_ENTITY => "VALIDATION_FAILED",
+            StatusCode::BAD_REQUEST => "INVALID_INPUT",
+            _ => "INTERNAL_ERROR",
+        }
+    }
+
+    /// Whether the error comes from our side. Its message is logged but never sent to clients.
+    pub fn is_internal(&self) -> bool {
+        actix_web::ResponseError::status_code(self).is_server_error()
+    }
+
+    /// The message that is safe to show to clients.
+    pub fn public_message(&self) -> String {
+        match self {
+            Error::Unauthorized(msg)
+            | Error::Forbidden(msg)
+            | Error::NotFound(msg)
+            | Error::Conflict(msg)
+            | Error::Validation(msg)
+            | Error::InvalidInput(msg) => msg.clone(),
+            Error::ValidationFailed(_) => "One or more fields are invalid".to_string(),
+            Error::Database(e) if is_unique_violation(e) => "A record with the same value already exists".to_string(),
+            Error::Database(sqlx::Error::RowNotFound) => "Not found".to_string(),
+            Error::Database(_) | Error::Io(_) | Error::Server(_) => "Internal server error".to_string(),
+        }
+    }
+
+    pub fn field_errors(&self) -> Option<Vec<FieldError>> {
+        match self {
+            Error::ValidationFailed(errors) => Some(errors.clone()),
+            Error::Database(sqlx::Error::Database(db_error)) if db_error.is_unique_violation() => {
+                unique_violation_field(db_error.as_ref())
+                    .map(|field| vec![FieldError::new(field, "already exists")])
+            }
+            _ => None,
+        }
+    }
+
+    pub fn to_problem(&self) -> ProblemDetails {
+        let status = actix_web::ResponseError::status_code(self);
+
+        ProblemDetails {
+            problem_type: "about:blank",
+            title: status.canonical_reason().unwrap_or("Error"),
+            status: status.as_u16(),
+            detail: self.public_message(),
+            code: self.code(),
+            request_id: request_id::current(),
+            details: self.field_errors(),
+        }
+    }
+}
+
+fn is_unique_violation(error: &sqlx::Error) -> bool {
+    matches!(error, sqlx::Error::Database(db_error) if db_error.is_unique_violation())
+}
+
+/// Recovers the column from Postgres' default `{table}_{column}_key` constraint names.
+fn unique_violation_field(db_error: &dyn sqlx::error::DatabaseError) -> Option<String> {
+    let constraint = db_error.constraint()?;
+    let without_suffix = constraint.strip_suffix("_key")?;
+
+    match db_error.table() {
+        Some(table) => without_suffix.strip_prefix(&format!("{}_", table)).map(str::to_string),
+        None => Some(without_suffix.to_string()),
+    }
+}
+
 impl actix_web::ResponseError for Error {
-    fn error_response(&self) -> HttpResponse {
+    fn status_code(&self) -> StatusCode {
         match self {
-            Error::Unauthorized(msg) => HttpResponse::Unauthorized().json(json!({
-                "error": msg
-            })),
-            Error::Forbidden(msg) => HttpResponse::Unauthorized().json(json!({
-                "error": msg
-            })),
-            Error::NotFound(msg) => HttpResponse::NotFound().json(json!({
-                "error": msg
-            })),
-            Error::Database(_) => HttpResponse::InternalServerError().json(json!({
-                "error": "Internal server error"
-            })),
-            Error::Validation(msg) => HttpResponse::BadRequest().json(json!({
-                "error": msg
-            })),
-            Error::Io(_) => HttpResponse::InternalServerError().json(json!({
-                "error": "Internal server error"
-            })),
-            Error::Server(msg) => HttpResponse::NotFound().json(json!({
-                "error": msg
-            })),
-            Error::InvalidInput(msg) => HttpResponse::BadRequest().json(json!({
-                "error": msg
-            })),
-        }
-    }
-}
\ No newline at end of file
+            Error::Unauthorized(_) => StatusCode::UNAUTHORIZED,
+            Error::Forbidden(_) => StatusCode::FORBIDDEN,
+            Error::NotFound(_) => StatusCode::NOT_FOUND,
+            Error::Conflict(_) => StatusCode::CONFLICT,
+            Error::Database(e) if is_unique_violation(e) => StatusCode::CONFLICT,
+            Error::Database(sqlx::Error::RowNotFound) => StatusCode::NOT_FOUND,
+            Error::Database(_) => StatusCode::INTERNAL_SERVER_ERROR,
+            Error::Validation(_) | Error::ValidationFailed(_) => StatusCode::UNPROCESSABLE_ENTITY,
+            Error::Io(_) => StatusCode::INTERNAL_SERVER_ERROR,
+            Error::Server(_) => StatusCode::INTERNAL_SERVER_ERROR,
+            Error::InvalidInput(_) => StatusCode::BAD_REQUEST,
+        }
+    }
+
+    fn error_response(&self) -> HttpResponse {
+        let problem = self.to_problem();
+
+        if self.is_internal() {
+            tracing::error!(error = %self, "Internal error");
+        }
+
+        HttpResponse::build(self.status_code())
+            .insert_header(ContentType(PROBLEM_JSON.parse().expect("valid mime type")))
+            .json(problem)
+    }
+}
+
+impl async_graphql::ErrorExtensions for Error {
+    fn extend(&self) -> async_graphql::Error {
+        let code = match actix_web::ResponseError::status_code(self) {
+            StatusCode::UNAUTHORIZED => "UNAUTHENTICATED",
+            StatusCode::FORBIDDEN => "FORBIDDEN",
+            StatusCode::NOT_FOUND => "NOT_FOUND",
+            status if status.is_client_error() => "BAD_USER_INPUT",
+            _ => "INTERNAL",
+        };
+
+        let correlation_id = self.is_internal().then(|| log_internal(self));
+
+        let details = self.field_errors()
+            .and_then(|errors| async_graphql::to_value(errors).ok());
+
+        async_graphql::Error::new(self.public_message()).extend_with(|_, extensions| {
+            extensions.set("code", code);
+            if let Some(id) = correlation_id {
+                extensions.set("correlationId", id);
+            }
+            if let Some(details) = details {
+                extensions.set("details", details);
+            }
+        })
+    }
+}
+
+/// Logs an internal error under a correlation id (the request id when there is one) that the
+/// client gets instead of the message.
+fn log_internal(error: &dyn std::fmt::Display) -> String {
+    let id = request_id::current().unwrap_or_else(|| uuid::Uuid::new_v4().to_string());
+    tracing::error!(correlation_id = %id, error = %error, "Internal error");
+    id
+}
+
+/// `async_graphql` extension that gives every error an `extensions.code`, so resolvers only need
+/// `.extend()` for the codes an `Error` maps to. A resolver error without one, such as a missing
+/// `ctx.data`, is treated as `INTERNAL`: its message is logged and replaced. Errors raised before execution, such as parse and validation errors, are `BAD_USER_INPUT`.
+pub struct GraphQLErrorCodes;
+
+impl ExtensionFactory for GraphQLErrorCodes {
+    fn create(&self) -> Arc<dyn Extension> {
+        Arc::new(GraphQLErrorCodes)
+    }
+}
+
+#[async_trait::async_trait]
+impl Extension for GraphQLErrorCodes {
+    async fn request(&self, ctx: &ExtensionContext<'_>, next: NextRequest<'_>) -> Response {
+        let mut response = next.run(ctx).await;
+
+        for error in response.errors.iter_mut().filter(|error| !has_code(error)) {
+            error.extensions.get_or_insert_with(Default::default).set("code", "BAD_USER_INPUT");
+        }
+
+        response
+    }
+
+    async fn resolve(
+        &self,
+        ctx: &ExtensionContext<'_>,
+        info: ResolveInfo<'_>,
+        next: NextResolve<'_>,
+    ) -> ServerResult<Option<Value>> {
+        next.run(ctx, info).await.map_err(|mut error| {
+            if !has_code(&error) {
+                let correlation_id = log_internal(&error.message);
+                error.message = "Internal server error".to_string();
+                let extensions = error.extensions.get_or_insert_with(Default::default);
+                extensions.set("code", "INTERNAL");
+                extensions.set("correlationId", correlation_id);
+            }
+            error
+        })
+    }
+}
+
+fn has_code(error: &ServerError) -> bool {
+    error.extensions.as_ref().is_some_and(|extensions| extensions.get("code").is_some())
+}
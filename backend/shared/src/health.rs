@@ -0,0 +1,94 @@
+use crate::{
+    config::AppConfig,
+    database::apply_migration::MigrationManager,
+    error::Error,
+};
+use actix_web::{web, HttpResponse};
+use serde::Serialize;
+use sqlx::PgPool;
+use std::{collections::BTreeMap, future::Future, time::{Duration, Instant}};
+
+/// Upper bound for each readiness check, so a hung database fails the probe instead of stalling it.
+const CHECK_TIMEOUT: Duration = Duration::from_secs(2);
+
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum HealthStatus {
+    Ok,
+    Error,
+}
+
+#[derive(Debug, Serialize)]
+pub struct ComponentHealth {
+    pub status: HealthStatus,
+    pub latency_ms: f64,
+    /// Safe for the unauthenticated probe: internal errors are logged and reported as `unavailable`.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct HealthReport {
+    pub status: HealthStatus,
+    pub checks: BTreeMap<&'static str, ComponentHealth>,
+}
+
+/// Liveness: the process is up and serving requests. Deliberately checks nothing else.
+pub async fn live() -> HttpResponse {
+    HttpResponse::Ok().json(serde_json::json!({ "status": HealthStatus::Ok }))
+}
+
+/// Readiness: the database answers and the schema is fully migrated. 503 if any check fails.
+pub async fn ready(pool: web::Data<PgPool>, config: web::Data<AppConfig>) -> HttpResponse {
+    let mut checks = BTreeMap::new();
+
+    checks.insert("database", check("database", async {
+        sqlx::query("SELECT 1").execute(pool.get_ref()).await?;
+        Ok(())
+    }).await);
+
+    checks.insert("migrations", check("migrations", async {
+        let manager = MigrationManager::new(config.database.migration_source());
+        let pending = manager.find_pending_up_migrations(&pool).await?;
+
+        if pending.is_empty() {
+            Ok(())
+        } else {
+            Err(Error::Conflict(format!("{} pending migration(s)", pending.len())))
+        }
+    }).await);
+
+    let healthy = checks.values().all(|check| matches!(check.status, HealthStatus::Ok));
+    let report = HealthReport {
+        status: if healthy { HealthStatus::Ok } else { HealthStatus::Error },
+        checks,
+    };
+
+    if healthy {
+        HttpResponse::Ok().json(report)
+    } else {
+        tracing::warn!(?report, "Readiness check failed");
+        HttpResponse::ServiceUnavailable().json(report)
+    }
+}
+
+async fn check<F>(component: &str, future: F) -> ComponentHealth
+where
+    F: Future<Output = Result<(), Error>>,
+{
+    let started = Instant::now();
+    let result = tokio::time::timeout(CHECK_TIMEOUT, future).await;
+    let latency_ms = started.elapsed().as_secs_f64() * 1000.0;
+
+    let (status, error) = match result {
+        Ok(Ok(())) => (HealthStatus::Ok, None),
+        Ok(Err(e)) => {
+            tracing::warn!(component, error = %e, "Health check failed");
+            let message = if e.is_internal() { "unavailable".to_string() } else { e.public_message() };
+            (HealthStatus::Error, Some(message))
+        }
+        Err(_) => (HealthStatus::Error, Some(format!("Timed out after {:?}", CHECK_TIMEOUT))),
+    };
+
+    ComponentHealth { status, latency_ms, error }
+}
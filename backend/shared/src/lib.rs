@@ -1,4 +1,10 @@
 pub mod database;
 pub mod models;
 pub mod auth;
-pub mod error;
\ No newline at end of file
+pub mod error;
+pub mod request_id;
+pub mod config;
+pub mod app;
+pub mod telemetry;
+pub mod metrics;
+pub mod health;
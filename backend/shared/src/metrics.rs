@@ -0,0 +1,283 @@
+use actix_web::{
+    body::MessageBody,
+    dev::{ServiceRequest, ServiceResponse},
+    middleware::Next,
+    http::Method,
+    web, Error as ActixError, HttpResponse,
+};
+use async_graphql::{
+    extensions::{Extension, ExtensionContext, ExtensionFactory, NextExecute, NextResolve, ResolveInfo},
+    Response, ServerResult, Value,
+};
+use prometheus::{
+    Encoder, HistogramOpts, HistogramVec, IntCounter, IntCounterVec, IntGauge, IntGaugeVec, Opts, Registry,
+    TextEncoder,
+};
+use sqlx::PgPool;
+use std::{
+    sync::{Arc, LazyLock, Mutex},
+    time::{Duration, Instant},
+};
+
+use crate::database::apply_migration::MigrationManager;
+
+const LATENCY_BUCKETS: &[f64] = &[0.005, 0.01, 0.025, 0.05, 0.1, 0.25, 0.5, 1.0, 2.5, 5.0, 10.0];
+const SCRAPE_QUERY_TIMEOUT: Duration = Duration::from_secs(2);
+/// GraphQL `operation` label for requests that don't resolve exactly one top-level field.
+const OTHER_LABEL: &str = "other";
+
+static METRICS: LazyLock<Metrics> = LazyLock::new(Metrics::new);
+
+/// Process-wide metrics, shared by every worker and exposed on `/metrics`.
+pub fn metrics() -> &'static Metrics {
+    &METRICS
+}
+
+pub struct Metrics {
+    registry: Registry,
+    pub http_requests_total: IntCounterVec,
+    pub http_request_duration_seconds: HistogramVec,
+    pub graphql_operations_total: IntCounterVec,
+    pub graphql_operation_duration_seconds: HistogramVec,
+    pub graphql_errors_total: IntCounterVec,
+    pub auth_failures_total: IntCounterVec,
+    pub db_pool_connections: IntGaugeVec,
+    pub db_pool_acquire_timeouts_total: IntCounter,
+    pub migration_version: IntGauge,
+}
+
+impl Metrics {
+    fn new() -> Self {
+        let registry = Registry::new();
+
+        let http_requests_total = IntCounterVec::new(
+            Opts::new("http_requests_total", "HTTP requests by method, route pattern and status"),
+            &["method", "route", "status"],
+        ).expect("valid metric");
+        let http_request_duration_seconds = HistogramVec::new(
+            HistogramOpts::new("http_request_duration_seconds", "HTTP request latency by method and route pattern")
+                .buckets(LATENCY_BUCKETS.to_vec()),
+            &["method", "route"],
+        ).expect("valid metric");
+        let graphql_operations_total = IntCounterVec::new(
+            Opts::new("graphql_operations_total", "GraphQL operations by top-level field and outcome"),
+            &["operation", "outcome"],
+        ).expect("valid metric");
+        let graphql_operation_duration_seconds = HistogramVec::new(
+            HistogramOpts::new("graphql_operation_duration_seconds", "GraphQL operation latency by top-level field")
+                .buckets(LATENCY_BUCKETS.to_vec()),
+            &["operation"],
+        ).expect("valid metric");
+        let graphql_errors_total = IntCounterVec::new(
+            Opts::new("graphql_errors_total", "GraphQL errors by operation and extensions.code"),
+            &["operation", "code"],
+        ).expect("valid metric");
+        let auth_failures_total = IntCounterVec::new(
+            Opts::new("auth_failures_total", "Rejected authentication attempts by reason"),
+            &["reason"],
+        ).expect("valid metric");
+        let db_pool_connections = IntGaugeVec::new(
+            Opts::new("db_pool_connections", "Database pool connections by state (size, idle, in_use, max)"),
+            &["state"],
+        ).expect("valid metric");
+        let db_pool_acquire_timeouts_total = IntCounter::new(
+            "db_pool_acquire_timeouts_total", "Requests that gave up waiting for a pool connection (database.acquire_timeout_secs)",
+        ).expect("valid metric");
+        let migration_version = IntGauge::new(
+            "migration_version", "Highest applied schema migration version",
+        ).expect("valid metric");
+
+        for collector in [
+            Box::new(http_requests_total.clone()) as Box<dyn prometheus::core::Collector>,
+            Box::new(http_request_duration_seconds.clone()),
+            Box::new(graphql_operations_total.clone()),
+            Box::new(graphql_operation_duration_seconds.clone()),
+            Box::new(graphql_errors_total.clone()),
+            Box::new(auth_failures_total.clone()),
+            Box::new(db_pool_connections.clone()),
+            Box::new(db_pool_acquire_timeouts_total.clone()),
+            Box::new(migration_version.clone()),
+        ] {
+            registry.register(collector).expect("metric registered once");
+        }
+
+        Self {
+            registry,
+            http_requests_total,
+            http_request_duration_seconds,
+            graphql_operations_total,
+            graphql_operation_duration_seconds,
+            graphql_errors_total,
+            auth_failures_total,
+            db_pool_connections,
+            db_pool_acquire_timeouts_total,
+            migration_version,
+        }
+    }
+
+    pub fn record_auth_failure(&self, reason: &str) {
+        self.auth_failures_total.with_label_values(&[reason]).inc();
+    }
+
+    /// Pool gauges are sampled at scrape time from the pool's own counters, without taking a connection.
+    fn observe_pool(&self, pool: &PgPool) {
+        let size = pool.size() as i64;
+        let idle = pool.num_idle() as i64;
+
+        self.db_pool_connections.with_label_values(&["size"]).set(size);
+        self.db_pool_connections.with_label_values(&["idle"]).set(idle);
+        self.db_pool_connections.with_label_values(&["in_use"]).set(size - idle);
+        self.db_pool_connections.with_label_values(&["max"]).set(pool.options().get_max_connections() as i64);
+    }
+
+    /// Sampled at scrape time so `migrate up` against a running server shows up; keeps the last value on failure.
+    async fn observe_migrations(&self, pool: &PgPool) {
+        match tokio::time::timeout(SCRAPE_QUERY_TIMEOUT, MigrationManager::get_applied_migrations(pool)).await {
+            Ok(Ok(versions)) => self.migration_version.set(versions.into_iter().max().unwrap_or(0)),
+            Ok(Err(e)) => tracing::warn!(error = %e, "Failed to read migration version for metrics"),
+            Err(_) => tracing::warn!("Timed out reading migration version for metrics"),
+        }
+    }
+
+    fn render(&self) -> Result<String, prometheus::Error> {
+        let mut buffer = Vec::new();
+        TextEncoder::new().encode(&self.registry.gather(), &mut buffer)?;
+
+        Ok(String::from_utf8_lossy(&buffer).into_owned())
+    }
+}
+
+/// Records count and latency per route pattern (`/api/v1/users/{id}`), so ids don't explode cardinality.
+pub async fn metrics_middleware(
+    req: ServiceRequest,
+    next: Next<impl MessageBody>,
+) -> Result<ServiceResponse<impl MessageBody>, ActixError> {
+    let method = method_label(req.method());
+    let started = Instant::now();
+
+    let res = next.call(req).await?;
+
+    let route = res.request().match_pattern().unwrap_or_else(|| "unmatched".to_string());
+    let metrics = metrics();
+    metrics.http_requests_total
+        .with_label_values(&[method, &route, res.status().as_str()])
+        .inc();
+    metrics.http_request_duration_seconds
+        .with_label_values(&[method, &route])
+        .observe(started.elapsed().as_secs_f64());
+
+    Ok(res)
+}
+
+/// Extension methods are accepted by actix, so anything non-standard is folded into one label.
+fn method_label(method: &Method) -> &'static str {
+    match *method {
+        Method::GET => "GET",
+        Method::POST => "POST",
+        Method::PUT => "PUT",
+        Method::PATCH => "PATCH",
+        Method::DELETE => "DELETE",
+        Method::HEAD => "HEAD",
+        Method::OPTIONS => "OPTIONS",
+        Method::CONNECT => "CONNECT",
+        Method::TRACE => "TRACE",
+        _ => "OTHER",
+    }
+}
+
+pub async fn metrics_handler(pool: web::Data<PgPool>) -> HttpResponse {
+    let metrics = metrics();
+    metrics.observe_pool(&pool);
+    metrics.observe_migrations(&pool).await;
+
+    match metrics.render() {
+        Ok(body) => HttpResponse::Ok()
+            .content_type(TextEncoder::new().format_type())
+            .body(body),
+        Err(e) => {
+            tracing::error!(error = %e, "Failed to encode metrics");
+            HttpResponse::InternalServerError().finish()
+        }
+    }
+}
+
+/// `async_graphql` extension recording operation count, duration and error codes.
+///
+/// The `operation` label is the top-level schema field that was resolved (`users`, `createUser`), not the
+/// client-chosen operation name, so it stays bounded; requests that resolve none or several are `other`.
+pub struct GraphQLMetrics;
+
+impl ExtensionFactory for GraphQLMetrics {
+    fn create(&self) -> Arc<dyn Extension> {
+        Arc::new(GraphQLMetricsExtension::default())
+    }
+}
+
+#[derive(Default)]
+struct GraphQLMetricsExtension {
+    top_level_fields: Mutex<Vec<String>>,
+}
+
+impl GraphQLMetricsExtension {
+    fn operation_label(&self) -> String {
+        let mut fields = self.top_level_fields.lock().expect("metrics mutex poisoned").clone();
+        fields.sort();
+        fields.dedup();
+
+        match fields.as_slice() {
+            [field] => field.clone(),
+            _ => OTHER_LABEL.to_string(),
+        }
+    }
+}
+
+#[async_trait::async_trait]
+impl Extension for GraphQLMetricsExtension {
+    async fn execute(
+        &self,
+        ctx: &ExtensionContext<'_>,
+        operation_name: Option<&str>,
+        next: NextExecute<'_>,
+    ) -> Response {
+        let started = Instant::now();
+
+        let response = next.run(ctx, operation_name).await;
+
+        let operation = self.operation_label();
+        let operation = operation.as_str();
+        let metrics = metrics();
+        metrics.graphql_operation_duration_seconds
+            .with_label_values(&[operation])
+            .observe(started.elapsed().as_secs_f64());
+
+        let outcome = if response.is_ok() { "ok" } else { "error" };
+        metrics.graphql_operations_total.with_label_values(&[operation, outcome]).inc();
+
+        for error in &response.errors {
+            let code = error.extensions.as_ref()
+                .and_then(|extensions| extensions.get("code"))
+                .and_then(|code| match code {
+                    Value::String(code) => Some(code.as_str()),
+                    _ => None,
+                })
+                .unwrap_or("UNKNOWN");
+            metrics.graphql_errors_total.with_label_values(&[operation, code]).inc();
+        }
+
+        response
+    }
+
+    async fn resolve(
+        &self,
+        ctx: &ExtensionContext<'_>,
+        info: ResolveInfo<'_>,
+        next: NextResolve<'_>,
+    ) -> ServerResult<Option<Value>> {
+        // Only fields that passed validation are resolved, so these names come from the schema.
+        if info.path_node.parent.is_none() {
+            self.top_level_fields.lock().expect("metrics mutex poisoned").push(info.name.to_string());
+        }
+
+        next.run(ctx, info).await
+    }
+}
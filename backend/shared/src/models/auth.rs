@@ -0,0 +1,11 @@
+use async_graphql::SimpleObject;
+use serde::Serialize;
+
+#[derive(SimpleObject, Serialize, Clone, Debug)]
+pub struct TokenPair {
+    pub access_token: String,
+    pub refresh_token: String,
+    pub token_type: String,
+    /// Access token lifetime in seconds.
+    pub expires_in: i64,
+}
@@ -1,3 +1,4 @@
 pub mod user;
 pub mod mutation;
-pub mod request;
\ No newline at end of file
+pub mod request;
+pub mod auth;
\ No newline at end of file
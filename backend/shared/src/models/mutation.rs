@@ -1,14 +1,18 @@
 use async_graphql::*;
 use crate::{
-    models::user::GraphQLUser,
-    database::services::user_service::UserService,
+    models::{user::GraphQLUser, auth::TokenPair, request::UpdateUserRequest},
+    database::{repositories::user_repository::UserChanges, services::user_service::UserService},
+    auth::{AuthService, Claims, CurrentUser, Role, Permission, PermissionGuard},
+    error::Error as AppError,
 };
+use uuid::Uuid;
 
 #[derive(Default)]
 pub struct Mutation;
 
 #[Object]
 impl Mutation {
+    #[graphql(guard = "PermissionGuard::new(Permission::CreateUser)")]
     async fn create_user(
         &self,
         ctx: &Context<'_>,
@@ -16,8 +20,154 @@ impl Mutation {
     ) -> Result<GraphQLUser> {
         let user_service = ctx.data::<UserService>()?;
 
-        let password_hash = format!("hashed_{}", input.password);
-        let user_profile = user_service.create(&input.username, &input.email, &password_hash).await?;
+        let user_profile = user_service.create(&input.username, &input.email, &input.password).await.extend()?;
+
+        Ok(user_profile.into())
+    }
+
+    async fn login(
+        &self,
+        ctx: &Context<'_>,
+        input: LoginInput,
+    ) -> Result<TokenPair> {
+        let auth_service = ctx.data::<AuthService>()?;
+
+        let token_pair = auth_service.login(&input.username, &input.password).await.extend()?;
+
+        Ok(token_pair)
+    }
+
+    async fn refresh_token(
+        &self,
+        ctx: &Context<'_>,
+        refresh_token: String,
+    ) -> Result<TokenPair> {
+        let auth_service = ctx.data::<AuthService>()?;
+
+        let token_pair = auth_service.refresh(&refresh_token).await.extend()?;
+
+        Ok(token_pair)
+    }
+
+    async fn logout(
+        &self,
+        ctx: &Context<'_>,
+        refresh_token: Option<String>,
+    ) -> Result<bool> {
+        let auth_service = ctx.data::<AuthService>()?;
+        let claims = ctx.data::<Claims>()
+            .map_err(|_| AppError::Unauthorized("Not authenticated".to_string()).extend())?;
+
+        auth_service.logout(claims, refresh_token.as_deref()).await.extend()?;
+
+        Ok(true)
+    }
+
+    /// Omitting `user_id` logs out the caller everywhere; naming another user requires the Admin role.
+    async fn logout_all(
+        &self,
+        ctx: &Context<'_>,
+        user_id: Option<ID>,
+    ) -> Result<bool> {
+        let auth_service = ctx.data::<AuthService>()?;
+        let current_user = ctx.data::<CurrentUser>()
+            .map_err(|_| AppError::Unauthorized("Not authenticated".to_string()).extend())?;
+
+        let target_id = match user_id {
+            Some(id) => Uuid::parse_str(&id)
+                .map_err(|_| AppError::InvalidInput("Invalid user id".to_string()).extend())?,
+            None => current_user.id,
+        };
+
+        auth_service.logout_all(current_user, target_id).await.extend()?;
+
+        Ok(true)
+    }
+
+    #[graphql(guard = "PermissionGuard::new(Permission::ManageRoles)")]
+    async fn assign_role(
+        &self,
+        ctx: &Context<'_>,
+        user_id: ID,
+        role: Role,
+    ) -> Result<GraphQLUser> {
+        let user_service = ctx.data::<UserService>()?;
+
+        user_service.assign_role(&user_id, role).await.extend()?;
+        let user_profile = user_service.find_by_id(&user_id).await.extend()?
+            .ok_or_else(|| AppError::NotFound("User not found".to_string()).extend())?;
+
+        Ok(user_profile.into())
+    }
+
+    /// The last Admin can't lose the Admin role.
+    #[graphql(guard = "PermissionGuard::new(Permission::ManageRoles)")]
+    async fn revoke_role(
+        &self,
+        ctx: &Context<'_>,
+        user_id: ID,
+        role: Role,
+    ) -> Result<GraphQLUser> {
+        let user_service = ctx.data::<UserService>()?;
+
+        user_service.revoke_role(&user_id, role).await.extend()?;
+        let user_profile = user_service.find_by_id(&user_id).await.extend()?
+            .ok_or_else(|| AppError::NotFound("User not found".to_string()).extend())?;
+
+        Ok(user_profile.into())
+    }
+
+    /// Omitting `userId` updates the caller's own profile; updating someone else requires `UpdateUser`.
+    async fn update_user(
+        &self,
+        ctx: &Context<'_>,
+        input: UpdateUserInput,
+    ) -> Result<GraphQLUser> {
+        let user_service = ctx.data::<UserService>()?;
+        let current_user = ctx.data::<CurrentUser>()
+            .map_err(|_| AppError::Unauthorized("Not authenticated".to_string()).extend())?;
+
+        let user_id = match &input.user_id {
+            Some(id) => id.to_string(),
+            None => current_user.id.to_string(),
+        };
+        let changes = input.into_changes().extend()?;
+
+        let user_profile = user_service.update(current_user, &user_id, changes).await.extend()?;
+
+        Ok(user_profile.into())
+    }
+
+    /// Soft-deletes the user; `purge: true` permanently removes a user that was already deleted.
+    #[graphql(guard = "PermissionGuard::new(Permission::DeleteUser)")]
+    async fn delete_user(
+        &self,
+        ctx: &Context<'_>,
+        user_id: ID,
+        #[graphql(default)] purge: bool,
+    ) -> Result<bool> {
+        let user_service = ctx.data::<UserService>()?;
+        let current_user = ctx.data::<CurrentUser>()?;
+
+        if purge {
+            user_service.purge(current_user, &user_id).await.extend()?;
+        } else {
+            user_service.soft_delete(current_user, &user_id).await.extend()?;
+        }
+
+        Ok(true)
+    }
+
+    #[graphql(guard = "PermissionGuard::new(Permission::DeleteUser)")]
+    async fn restore_user(
+        &self,
+        ctx: &Context<'_>,
+        user_id: ID,
+    ) -> Result<GraphQLUser> {
+        let user_service = ctx.data::<UserService>()?;
+        let current_user = ctx.data::<CurrentUser>()?;
+
+        let user_profile = user_service.restore(current_user, &user_id).await.extend()?;
 
         Ok(user_profile.into())
     }
@@ -28,4 +178,28 @@ pub struct CreateUserInput {
     pub username: String,
     pub email: String,
     pub password: String,
-}
\ No newline at end of file
+}
+
+/// Fields left out stay unchanged; an explicit `null` is rejected like `null` in the REST merge patch.
+#[derive(InputObject)]
+pub struct UpdateUserInput {
+    pub user_id: Option<ID>,
+    pub username: MaybeUndefined<String>,
+    pub email: MaybeUndefined<String>,
+}
+
+impl UpdateUserInput {
+    fn into_changes(self) -> Result<UserChanges, AppError> {
+        UpdateUserRequest {
+            username: self.username.into(),
+            email: self.email.into(),
+        }.into_changes()
+    }
+}
+
+/// `username` accepts either the username or the email address.
+#[derive(InputObject)]
+pub struct LoginInput {
+    pub username: String,
+    pub password: String,
+}
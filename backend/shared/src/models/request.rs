@@ -1,8 +1,87 @@
-use serde::Deserialize;
+use serde::{Deserialize, Deserializer};
+use crate::{
+    database::repositories::user_repository::UserChanges,
+    error::{Error, FieldError},
+};
 
 #[derive(Deserialize)]
 pub struct CreateUserRequest {
     pub username: String,
     pub email: String,
     pub password: String,
-}
\ No newline at end of file
+}
+
+/// `username` accepts either the username or the email address.
+#[derive(Deserialize)]
+pub struct LoginRequest {
+    pub username: String,
+    pub password: String,
+}
+
+#[derive(Deserialize)]
+pub struct RefreshTokenRequest {
+    pub refresh_token: String,
+}
+
+#[derive(Deserialize)]
+pub struct LogoutRequest {
+    pub refresh_token: Option<String>,
+}
+
+/// Omitting `user_id` logs out the caller; naming another user requires the Admin role.
+#[derive(Deserialize)]
+pub struct LogoutAllRequest {
+    pub user_id: Option<String>,
+}
+
+/// `?purge=true` permanently removes a user that was already soft-deleted.
+#[derive(Deserialize)]
+pub struct DeleteUserQuery {
+    #[serde(default)]
+    pub purge: bool,
+}
+
+/// JSON Merge Patch (RFC 7396) body for `PATCH /users/{id}`. Absent members stay unchanged; `null`
+/// would remove a member, which none of these fields allow.
+#[derive(Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct UpdateUserRequest {
+    #[serde(default, deserialize_with = "present")]
+    pub username: Option<Option<String>>,
+    #[serde(default, deserialize_with = "present")]
+    pub email: Option<Option<String>>,
+}
+
+impl UpdateUserRequest {
+    pub fn into_changes(self) -> Result<UserChanges, Error> {
+        let mut errors = Vec::new();
+        let mut required = |field: &str, value: Option<Option<String>>| match value {
+            Some(None) => {
+                errors.push(FieldError::new(field, "cannot be removed"));
+                None
+            }
+            value => value.flatten(),
+        };
+
+        let changes = UserChanges {
+            username: required("username", self.username),
+            email: required("email", self.email),
+        };
+
+        if errors.is_empty() {
+            Ok(changes)
+        } else {
+            Err(Error::ValidationFailed(errors))
+        }
+    }
+}
+
+/// Maps a member that is present to `Some`, so an explicit `null` becomes `Some(None)` while a
+/// missing member falls back to `None` through `#[serde(default)]`.
+fn present<'de, D, T>(deserializer: D) -> Result<Option<Option<T>>, D::Error>
+where
+    D: Deserializer<'de>,
+    T: Deserialize<'de>,
+{
+    Option::<T>::deserialize(deserializer).map(Some)
+}
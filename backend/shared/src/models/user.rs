@@ -3,9 +3,13 @@ use time::{OffsetDateTime,
 };
 use serde::{Serialize, Deserialize};
 use crate::{
-    database::models::db_user::DbUser,
+    auth::Role,
+    database::{
+        models::db_user::DbUser,
+        services::user_service::UserService,
+    },
 };
-use async_graphql::{Scalar, ScalarType, InputValueError, InputValueResult, Value, ID, SimpleObject, ComplexObject};
+use async_graphql::{Scalar, ScalarType, InputValueError, InputValueResult, Value, ID, SimpleObject, ComplexObject, Context, Result, ResultExt};
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct UserProfile {
@@ -81,6 +85,11 @@ impl GraphQLUser {
     async fn display_name(&self) -> String {
         format!("@{}", self.username)
     }
+    async fn roles(&self, ctx: &Context<'_>) -> Result<Vec<Role>> {
+        let user_service = ctx.data::<UserService>()?;
+
+        user_service.find_roles(&self.id).await.extend()
+    }
 }
 
 #[derive(Serialize)]
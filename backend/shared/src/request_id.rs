@@ -0,0 +1,71 @@
+use actix_web::{
+    body::{BoxBody, MessageBody},
+    dev::{ServiceRequest, ServiceResponse},
+    http::header::{HeaderName, HeaderValue},
+    middleware::Next,
+    Error as ActixError,
+    HttpMessage,
+};
+use std::time::Instant;
+use tracing::Instrument;
+use uuid::Uuid;
+
+pub const REQUEST_ID_HEADER: HeaderName = HeaderName::from_static("x-request-id");
+const MAX_REQUEST_ID_LEN: usize = 128;
+
+tokio::task_local! {
+    static REQUEST_ID: String;
+}
+
+/// Id of the request being handled, stored in request extensions by `request_id_middleware`.
+#[derive(Debug, Clone)]
+pub struct RequestId(pub String);
+
+/// Id of the request the current task is serving, if any. Lets code without access to the
+/// `HttpRequest` (e.g. `ResponseError::error_response`) tag its output.
+pub fn current() -> Option<String> {
+    REQUEST_ID.try_with(|id| id.clone()).ok()
+}
+
+/// Reuses a well-formed incoming `X-Request-Id` or generates one, exposes it to the rest of the
+/// request (including as the `request_id` field of its tracing span) and echoes it on the response. Wrap it outermost; errors are only tagged with the id
+/// if they are rendered inside it (handlers do this, middleware should use `req.error_response`).
+pub async fn request_id_middleware(
+    req: ServiceRequest,
+    next: Next<impl MessageBody + 'static>,
+) -> Result<ServiceResponse<BoxBody>, ActixError> {
+    let request_id = req.headers().get(&REQUEST_ID_HEADER)
+        .and_then(|value| value.to_str().ok())
+        .filter(|value| !value.is_empty()
+            && value.len() <= MAX_REQUEST_ID_LEN
+            && value.bytes().all(|b| b.is_ascii_alphanumeric() || b == b'-' || b == b'_' || b == b'.'))
+        .map(str::to_string)
+        .unwrap_or_else(|| Uuid::new_v4().to_string());
+
+    req.extensions_mut().insert(RequestId(request_id.clone()));
+
+    let span = tracing::info_span!(
+        "http_request",
+        request_id = %request_id,
+        method = %req.method(),
+        path = %req.path(),
+    );
+    let started = Instant::now();
+
+    let mut res = REQUEST_ID.scope(request_id.clone(), next.call(req))
+        .instrument(span.clone())
+        .await?
+        .map_into_boxed_body();
+
+    span.in_scope(|| tracing::info!(
+        status = res.status().as_u16(),
+        elapsed_ms = started.elapsed().as_millis() as u64,
+        "요청 완료",
+    ));
+
+    if let Ok(value) = HeaderValue::from_str(&request_id) {
+        res.headers_mut().insert(REQUEST_ID_HEADER, value);
+    }
+
+    Ok(res)
+}
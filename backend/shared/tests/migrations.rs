@@ -0,0 +1,250 @@
+//! Runs the embedded migrations against a real Postgres. Each test creates its own database on the
+//! server named by `DATABASE_URL` and drops it afterwards, even when the test panics. The tests are
+//! `#[ignore]`d so a plain `cargo test` doesn't need a server; run them with `-- --ignored`.
+
+use shared::database::{
+    apply_migration::{Direction, Drift, MigrationManager},
+    migration_source::{Migration, MigrationSource},
+};
+use sqlx::{
+    postgres::{PgConnectOptions, PgPoolOptions},
+    ConnectOptions, Connection, PgConnection, PgPool,
+};
+use std::{path::PathBuf, str::FromStr};
+
+struct TestDb {
+    admin: PgConnectOptions,
+    name: String,
+    pool: PgPool,
+}
+
+impl TestDb {
+    async fn create() -> Self {
+        let url = std::env::var("DATABASE_URL").expect("DATABASE_URL must point at a Postgres server for migration tests");
+        let admin = PgConnectOptions::from_str(&url).expect("valid DATABASE_URL");
+        let name = format!("migration_test_{}", uuid::Uuid::new_v4().simple());
+
+        let mut conn = admin.connect().await.expect("connect to DATABASE_URL");
+        sqlx::raw_sql(&format!("CREATE DATABASE {}", name)).execute(&mut conn).await.expect("create test database");
+        conn.close().await.ok();
+
+        let pool = PgPoolOptions::new()
+            .max_connections(4)
+            .connect_with(admin.clone().database(&name))
+            .await
+            .expect("connect to test database");
+        MigrationManager::ensure_migration_table(&pool).await.expect("create tracking table");
+
+        Self { admin, name, pool }
+    }
+
+    async fn tables(&self) -> Vec<String> {
+        sqlx::query_scalar(
+            "SELECT table_name::TEXT FROM information_schema.tables
+             WHERE table_schema = 'public' AND table_name <> '_schema_migrations'
+             ORDER BY table_name"
+        )
+        .fetch_all(&self.pool)
+        .await
+        .expect("list tables")
+    }
+}
+
+/// Drops the database when the test ends, also on panic. `Drop` can't await, and blocking the test's own
+/// runtime would deadlock, so the drop runs on a short-lived runtime in a separate thread.
+impl Drop for TestDb {
+    fn drop(&mut self) {
+        let admin = self.admin.clone();
+        let name = self.name.clone();
+
+        let dropped = std::thread::spawn(move || {
+            tokio::runtime::Builder::new_current_thread().enable_all().build()?.block_on(async {
+                let mut conn: PgConnection = admin.connect().await?;
+                sqlx::raw_sql(&format!("DROP DATABASE IF EXISTS {} WITH (FORCE)", name)).execute(&mut conn).await?;
+                conn.close().await
+            })
+            .map_err(std::io::Error::other)
+        })
+        .join();
+
+        if !matches!(dropped, Ok(Ok(()))) {
+            eprintln!("failed to drop test database {}: {:?}", self.name, dropped);
+        }
+    }
+}
+
+fn manager() -> MigrationManager {
+    MigrationManager::new(MigrationSource::Embedded)
+}
+
+/// Copies the embedded migrations into a fresh directory so a test can edit them.
+async fn filesystem_copy() -> (PathBuf, Vec<Migration>) {
+    let dir = std::env::temp_dir().join(format!("migrations_{}", uuid::Uuid::new_v4().simple()));
+    tokio::fs::create_dir_all(&dir).await.unwrap();
+    let migrations = MigrationSource::Embedded.load().await.unwrap();
+    for migration in &migrations {
+        tokio::fs::write(dir.join(&migration.up_file), &migration.up_sql).await.unwrap();
+        if let Some(down_sql) = &migration.down_sql {
+            tokio::fs::write(dir.join(migration.down_file()), down_sql).await.unwrap();
+        }
+    }
+
+    (dir, migrations)
+}
+
+#[tokio::test]
+#[ignore = "needs a Postgres server in DATABASE_URL"]
+async fn every_migration_round_trips_up_and_down() {
+    let db = TestDb::create().await;
+    let manager = manager();
+    let migrations = MigrationSource::Embedded.load().await.unwrap();
+    assert!(!migrations.is_empty());
+
+    let mut previous = 0;
+    for migration in &migrations {
+        let before = db.tables().await;
+
+        manager.run_up_to(&db.pool, Some(migration.version)).await.unwrap();
+        let applied = db.tables().await;
+        manager.rollback_to(previous, &db.pool).await.unwrap();
+        assert_eq!(db.tables().await, before, "{} down did not undo up", migration.up_file);
+
+        manager.run_up_to(&db.pool, Some(migration.version)).await.unwrap();
+        assert_eq!(db.tables().await, applied, "{} did not re-apply cleanly", migration.up_file);
+        assert_eq!(MigrationManager::get_applied_migrations(&db.pool).await.unwrap().last(), Some(&migration.version));
+
+        previous = migration.version;
+    }
+
+    manager.rollback_to(0, &db.pool).await.unwrap();
+    assert!(db.tables().await.is_empty());
+    assert!(MigrationManager::get_applied_migrations(&db.pool).await.unwrap().is_empty());
+
+}
+
+#[tokio::test]
+#[ignore = "needs a Postgres server in DATABASE_URL"]
+async fn rollback_to_keeps_the_target_version() {
+    let db = TestDb::create().await;
+    let manager = manager();
+
+    manager.run_pending_up_migrations(&db.pool).await.unwrap();
+    manager.rollback_to(3, &db.pool).await.unwrap();
+
+    assert_eq!(MigrationManager::get_applied_migrations(&db.pool).await.unwrap(), vec![1, 2, 3]);
+    assert!(manager.find_pending_up_migrations(&db.pool).await.unwrap().iter().all(|(version, _)| *version > 3));
+
+}
+
+#[tokio::test]
+#[ignore = "needs a Postgres server in DATABASE_URL"]
+async fn plans_list_steps_without_running_them() {
+    let db = TestDb::create().await;
+    let manager = manager();
+    let migrations = MigrationSource::Embedded.load().await.unwrap();
+
+    let up = manager.plan_up(&db.pool, None).await.unwrap();
+    assert_eq!(
+        up.steps.iter().map(|step| step.migration.version).collect::<Vec<_>>(),
+        migrations.iter().map(|migration| migration.version).collect::<Vec<_>>(),
+    );
+    assert!(up.steps.iter().all(|step| step.direction == Direction::Up));
+    assert!(MigrationManager::get_applied_migrations(&db.pool).await.unwrap().is_empty());
+
+    manager.execute_plan(&db.pool, &up).await.unwrap();
+
+    let down = manager.plan_down(&db.pool, 5).await.unwrap();
+    let latest = migrations.last().unwrap().version;
+    assert_eq!(
+        down.steps.iter().map(|step| step.migration.version).collect::<Vec<_>>(),
+        (6..=latest).rev().collect::<Vec<_>>(),
+    );
+    assert!(down.steps.iter().all(|step| step.direction == Direction::Down && !step.sql().is_empty()));
+    assert_eq!(MigrationManager::get_applied_migrations(&db.pool).await.unwrap().last(), Some(&latest));
+
+    let redo = manager.plan_redo(&db.pool).await.unwrap();
+    assert_eq!(
+        redo.steps.iter().map(|step| (step.direction, step.migration.version)).collect::<Vec<_>>(),
+        vec![(Direction::Down, latest), (Direction::Up, latest)],
+    );
+
+}
+
+#[tokio::test]
+#[ignore = "needs a Postgres server in DATABASE_URL"]
+async fn concurrent_runs_apply_each_migration_once() {
+    let db = TestDb::create().await;
+
+    let run = |pool: PgPool| async move {
+        let lock = MigrationManager::lock(&pool).await?;
+        manager().run_pending_up_migrations(&pool).await?;
+        lock.release().await
+    };
+    let (first, second) = tokio::join!(run(db.pool.clone()), run(db.pool.clone()));
+    first.unwrap();
+    second.unwrap();
+
+    let applied = MigrationManager::get_applied_migrations(&db.pool).await.unwrap();
+    let expected: Vec<i64> = MigrationSource::Embedded.load().await.unwrap().iter().map(|m| m.version).collect();
+    assert_eq!(applied, expected);
+
+}
+
+#[tokio::test]
+#[ignore = "needs a Postgres server in DATABASE_URL"]
+async fn baseline_replaces_covered_migrations_on_fresh_databases() {
+    let db = TestDb::create().await;
+    let replayed = TestDb::create().await;
+
+    let (dir, migrations) = filesystem_copy().await;
+    let manager = MigrationManager::new(MigrationSource::Filesystem(dir.clone()));
+    let versions: Vec<i64> = migrations.iter().map(|migration| migration.version).collect();
+    let latest = *versions.last().unwrap();
+
+    manager.create_baseline(&std::env::var("DATABASE_URL").unwrap(), None).await.unwrap();
+
+    let plan = manager.plan_up(&db.pool, None).await.unwrap();
+    assert_eq!(plan.steps.len(), 1);
+    assert_eq!((plan.steps[0].direction, plan.steps[0].migration.version), (Direction::Baseline, latest));
+    assert_eq!(plan.steps[0].covers.iter().map(|migration| migration.version).collect::<Vec<_>>(), versions);
+
+    manager.execute_plan(&db.pool, &plan).await.unwrap();
+    assert_eq!(MigrationManager::get_applied_migrations(&db.pool).await.unwrap(), versions);
+    assert!(manager.find_drift(&db.pool).await.unwrap().is_empty());
+
+    manager.run_pending_up_migrations(&replayed.pool).await.unwrap();
+    assert_eq!(db.tables().await, replayed.tables().await);
+
+    // Once a database has history, the individual files are used again.
+    manager.rollback_to(versions[0], &db.pool).await.unwrap();
+    let plan = manager.plan_up(&db.pool, None).await.unwrap();
+    assert!(plan.steps.iter().all(|step| step.direction == Direction::Up));
+    manager.execute_plan(&db.pool, &plan).await.unwrap();
+
+    tokio::fs::remove_dir_all(&dir).await.unwrap();
+}
+
+#[tokio::test]
+#[ignore = "needs a Postgres server in DATABASE_URL"]
+async fn edited_down_scripts_are_drift_until_repaired() {
+    let db = TestDb::create().await;
+    let (dir, migrations) = filesystem_copy().await;
+    let manager = MigrationManager::new(MigrationSource::Filesystem(dir.clone()));
+
+    manager.run_pending_up_migrations(&db.pool).await.unwrap();
+    assert!(manager.find_drift(&db.pool).await.unwrap().is_empty());
+
+    let edited = migrations.iter().find(|migration| migration.down_sql.is_some()).unwrap();
+    tokio::fs::write(dir.join(edited.down_file()), "SELECT 1;").await.unwrap();
+
+    let drift = manager.find_drift(&db.pool).await.unwrap();
+    assert!(
+        matches!(drift.as_slice(), [Drift::Changed { version, file, .. }] if *version == edited.version && *file == edited.down_file()),
+        "unexpected drift: {:?}", drift,
+    );
+
+    assert_eq!(manager.repair(&db.pool).await.unwrap(), vec![edited.version]);
+    assert!(manager.find_drift(&db.pool).await.unwrap().is_empty());
+
+    tokio::fs::remove_dir_all(&dir).await.unwrap();
+}